@@ -1,13 +1,130 @@
+use chrono::{DateTime, SecondsFormat, Utc};
 use reqwest::Client;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::Value;
+use std::collections::HashMap;
 
-use crate::error::AppResult;
+use crate::error::{AppError, AppResult};
+
+const LIVE_URL: &str = "https://api.alpaca.markets";
+const PAPER_URL: &str = "https://paper-api.alpaca.markets";
+const DATA_URL: &str = "https://data.alpaca.markets";
+const CRYPTO_DATA_PATH: &str = "/v1beta3/crypto/us";
+const STREAM_DATA_URL: &str = "wss://stream.data.alpaca.markets/v2";
+const LIVE_STREAM_URL: &str = "wss://api.alpaca.markets/stream";
+const PAPER_STREAM_URL: &str = "wss://paper-api.alpaca.markets/stream";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Environment {
+  Live,
+  Paper,
+}
+
+impl Environment {
+  pub fn base_url(&self) -> &'static str {
+    match self {
+      Environment::Live => LIVE_URL,
+      Environment::Paper => PAPER_URL,
+    }
+  }
+
+  /// Websocket endpoint for the account's `trade_updates` stream.
+  pub fn stream_url(&self) -> &'static str {
+    match self {
+      Environment::Live => LIVE_STREAM_URL,
+      Environment::Paper => PAPER_STREAM_URL,
+    }
+  }
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      Environment::Live => "LIVE",
+      Environment::Paper => "PAPER",
+    }
+  }
+}
+
+/// Crypto pairs are written with a slash, e.g. `BTC/USD`; stock tickers never contain one.
+pub fn is_crypto(symbol: &str) -> bool {
+  symbol.contains('/')
+}
+
+/// Quote currencies of Alpaca's crypto pairs, used to tell `BTC-USD` apart from share classes like `BRK-B`.
+const CRYPTO_QUOTE_CURRENCIES: [&str; 4] = ["USD", "USDT", "USDC", "BTC"];
+
+/// Uppercases a symbol and accepts `BTC-USD` as an alias for the `BTC/USD` crypto pair.
+pub fn normalize_symbol(symbol: &str) -> String {
+  let symbol = symbol.trim().to_uppercase();
+  match symbol.split_once('-') {
+    Some((base, quote)) if !base.is_empty() && CRYPTO_QUOTE_CURRENCIES.contains(&quote) => {
+      format!("{base}/{quote}")
+    }
+    _ => symbol,
+  }
+}
+
+// Trading endpoints that take the symbol in the path want `BTCUSD` rather than `BTC/USD`
+fn path_symbol(symbol: &str) -> String {
+  symbol.replace('/', "")
+}
+
+/// Market data feed. SIP needs a paid subscription; `test` streams the fake `FAKEPACA` symbol around the clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feed {
+  #[default]
+  Iex,
+  Sip,
+  DelayedSip,
+  Test,
+}
+
+impl Feed {
+  pub fn from_arg(value: &str) -> Self {
+    match value {
+      "sip" => Feed::Sip,
+      "delayed_sip" => Feed::DelayedSip,
+      "test" => Feed::Test,
+      _ => Feed::Iex,
+    }
+  }
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Feed::Iex => "iex",
+      Feed::Sip => "sip",
+      Feed::DelayedSip => "delayed_sip",
+      Feed::Test => "test",
+    }
+  }
+
+  pub fn stream_url(&self) -> String {
+    format!("{}/{}", STREAM_DATA_URL, self.as_str())
+  }
+
+  // Historical data only distinguishes IEX from SIP; free plans get SIP once it is 15 minutes old
+  fn rest_param(&self) -> &'static str {
+    match self {
+      Feed::Sip | Feed::DelayedSip => "sip",
+      Feed::Iex | Feed::Test => "iex",
+    }
+  }
+}
+
+/// Options for `fetch_bars` beyond the symbol, timeframe and time range.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BarsOptions<'a> {
+  /// `raw`, `split` or `all`; stocks only.
+  pub adjustment: Option<&'a str>,
+  pub feed: Feed,
+}
 
 pub struct AlpacaClient {
   api_key: String,
   api_secret: String,
   base_url: String,
+  data_url: String,
   client: Client,
   pub base_stocks: [&'static str; 500],
 }
@@ -23,13 +140,50 @@ pub struct Asset {
   pub fractionable: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Account {
+  pub id: String,
+  pub account_number: String,
+  pub status: String,
+  pub currency: String,
+  pub cash: Decimal,
+  pub buying_power: Decimal,
+  pub equity: Decimal,
+  pub last_equity: Decimal,
+  pub portfolio_value: Option<Decimal>,
+  pub daytrade_count: i64,
+  pub pattern_day_trader: bool,
+  pub trading_blocked: bool,
+  pub account_blocked: bool,
+}
+
+impl Account {
+  /// Change in equity since the previous trading day's close, as (amount, percent).
+  pub fn equity_change(&self) -> Option<(Decimal, Decimal)> {
+    if self.last_equity.is_zero() {
+      return None;
+    }
+
+    let change = self.equity - self.last_equity;
+    Some((change, change / self.last_equity * Decimal::ONE_HUNDRED))
+  }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Position {
   pub symbol: String,
-  pub qty: String,
-  pub market_value: String,
-  pub unrealized_pl: String,
-  pub current_price: String,
+  pub side: Option<String>,
+  pub qty: Decimal,
+  pub avg_entry_price: Decimal,
+  pub cost_basis: Decimal,
+  pub market_value: Decimal,
+  pub current_price: Decimal,
+  pub unrealized_pl: Decimal,
+  pub unrealized_plpc: Decimal,
+  #[serde(default)]
+  pub unrealized_intraday_pl: Decimal,
+  #[serde(default)]
+  pub unrealized_intraday_plpc: Decimal,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,15 +191,394 @@ pub struct Order {
   pub id: String,
   pub symbol: String,
   pub side: String,
-  pub qty: Option<String>,
-  pub notional: Option<String>,
+  pub qty: Option<Decimal>,
+  pub notional: Option<Decimal>,
   pub status: String,
   #[serde(rename = "type")]
   pub order_type: String,
+  pub time_in_force: Option<String>,
+  pub limit_price: Option<Decimal>,
+  pub stop_price: Option<Decimal>,
+  pub trail_price: Option<Decimal>,
+  pub trail_percent: Option<Decimal>,
+  pub order_class: Option<String>,
+  pub legs: Option<Vec<Order>>,
+  #[serde(default)]
+  pub filled_qty: Option<Decimal>,
+  pub filled_avg_price: Option<Decimal>,
   pub filled_at: Option<String>,
   pub created_at: String,
 }
 
+impl Order {
+  /// Whether the order can no longer change (a replaced order lives on under a new ID).
+  pub fn is_terminal(&self) -> bool {
+    matches!(
+      self.status.as_str(),
+      "filled" | "canceled" | "expired" | "rejected" | "replaced"
+    )
+  }
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct OrderRequest {
+  pub symbol: String,
+  pub side: String,
+  #[serde(rename = "type")]
+  pub order_type: String,
+  pub time_in_force: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub qty: Option<Decimal>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub notional: Option<Decimal>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub limit_price: Option<Decimal>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub stop_price: Option<Decimal>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub trail_price: Option<Decimal>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub trail_percent: Option<Decimal>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub order_class: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub take_profit: Option<TakeProfit>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub stop_loss: Option<StopLoss>,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct ReplaceOrderRequest {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub qty: Option<Decimal>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub time_in_force: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub limit_price: Option<Decimal>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub stop_price: Option<Decimal>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub trail: Option<Decimal>,
+}
+
+impl ReplaceOrderRequest {
+  pub fn validate(&self) -> AppResult<()> {
+    let fields = [self.qty, self.limit_price, self.stop_price, self.trail];
+    if fields.iter().all(Option::is_none) && self.time_in_force.is_none() {
+      return Err(AppError::InvalidOrder("nothing to replace".into()));
+    }
+
+    if fields.iter().flatten().any(|v| v <= &Decimal::ZERO) {
+      return Err(AppError::InvalidOrder("replacement values must be greater than zero".into()));
+    }
+
+    Ok(())
+  }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TakeProfit {
+  pub limit_price: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StopLoss {
+  pub stop_price: Decimal,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub limit_price: Option<Decimal>,
+}
+
+impl OrderRequest {
+  pub fn market(side: &str, symbol: &str, notional: Decimal) -> Self {
+    Self {
+      symbol: symbol.to_string(),
+      side: side.to_string(),
+      order_type: "market".to_string(),
+      time_in_force: "day".to_string(),
+      notional: Some(notional),
+      ..Default::default()
+    }
+  }
+
+  /// Checks the combination of order type, prices and time in force before it is sent to Alpaca.
+  pub fn validate(&self) -> AppResult<()> {
+    let invalid = |msg: &str| Err(AppError::InvalidOrder(msg.to_string()));
+
+    let prices = [
+      ("limit price", self.limit_price),
+      ("stop price", self.stop_price),
+      ("trail price", self.trail_price),
+      ("trail percent", self.trail_percent),
+      ("qty", self.qty),
+      ("notional", self.notional),
+      ("take profit", self.take_profit.as_ref().map(|tp| tp.limit_price)),
+      ("stop loss", self.stop_loss.as_ref().map(|sl| sl.stop_price)),
+      ("stop loss limit", self.stop_loss.as_ref().and_then(|sl| sl.limit_price)),
+    ];
+    for (name, value) in prices {
+      if let Some(v) = value {
+        if v <= Decimal::ZERO {
+          return Err(AppError::InvalidOrder(format!("{name} must be greater than zero")));
+        }
+      }
+    }
+
+    if self.qty.is_some() == self.notional.is_some() {
+      return invalid("orders require exactly one of qty or notional");
+    }
+
+    let order_class = self.order_class.as_deref().unwrap_or("simple");
+    let has_limit = self.limit_price.is_some();
+    let has_stop = self.stop_price.is_some();
+    let has_trail = self.trail_price.is_some() || self.trail_percent.is_some();
+
+    match self.order_type.as_str() {
+      "market" => {
+        if has_limit || has_stop || has_trail {
+          return invalid("market orders do not take a limit, stop or trail price");
+        }
+      }
+      "limit" => {
+        // OCO orders take their limit from the take-profit leg
+        if !has_limit && order_class != "oco" {
+          return invalid("limit orders require --limit-price");
+        }
+        if has_stop || has_trail {
+          return invalid("limit orders only take --limit-price");
+        }
+      }
+      "stop" => {
+        if !has_stop {
+          return invalid("stop orders require --stop-price");
+        }
+        if has_limit || has_trail {
+          return invalid("stop orders only take --stop-price");
+        }
+      }
+      "stop_limit" => {
+        if !has_limit || !has_stop {
+          return invalid("stop_limit orders require both --limit-price and --stop-price");
+        }
+        if has_trail {
+          return invalid("stop_limit orders do not take a trail price");
+        }
+      }
+      "trailing_stop" => {
+        if self.trail_price.is_some() == self.trail_percent.is_some() {
+          return invalid("trailing_stop orders require exactly one of --trail-price or --trail-percent");
+        }
+        if has_limit || has_stop {
+          return invalid("trailing_stop orders do not take a limit or stop price");
+        }
+        if !matches!(self.time_in_force.as_str(), "day" | "gtc") {
+          return invalid("trailing_stop orders only support day or gtc time in force");
+        }
+      }
+      other => {
+        return Err(AppError::InvalidOrder(format!("unknown order type '{other}'")));
+      }
+    }
+
+    if is_crypto(&self.symbol) {
+      return self.validate_crypto(order_class);
+    }
+
+    if matches!(self.time_in_force.as_str(), "opg" | "cls")
+      && !matches!(self.order_type.as_str(), "market" | "limit")
+    {
+      return invalid("opg and cls time in force only apply to market and limit orders");
+    }
+
+    if self.notional.is_some() && self.time_in_force != "day" {
+      return invalid("notional orders only support day time in force");
+    }
+
+    if self.qty.is_some_and(|q| !q.fract().is_zero()) && self.time_in_force != "day" {
+      return invalid("fractional qty orders only support day time in force");
+    }
+
+    self.validate_order_class(order_class)
+  }
+
+  // Crypto trades around the clock, so fractional and notional orders are fine with gtc
+  fn validate_crypto(&self, order_class: &str) -> AppResult<()> {
+    let invalid = |msg: &str| Err(AppError::InvalidOrder(msg.to_string()));
+
+    if !matches!(self.order_type.as_str(), "market" | "limit" | "stop_limit") {
+      return invalid("crypto orders only support market, limit and stop_limit types");
+    }
+    if !matches!(self.time_in_force.as_str(), "gtc" | "ioc") {
+      return invalid("crypto orders only support gtc or ioc time in force");
+    }
+    if order_class != "simple" || self.take_profit.is_some() || self.stop_loss.is_some() {
+      return invalid("crypto orders do not support order classes");
+    }
+
+    Ok(())
+  }
+
+  fn validate_order_class(&self, order_class: &str) -> AppResult<()> {
+    let invalid = |msg: &str| Err(AppError::InvalidOrder(msg.to_string()));
+
+    let has_take_profit = self.take_profit.is_some();
+    let has_stop_loss = self.stop_loss.is_some();
+
+    if order_class == "simple" {
+      if has_take_profit || has_stop_loss {
+        return invalid("--take-profit and --stop-loss require --class bracket, oco or oto");
+      }
+      return Ok(());
+    }
+
+    if self.qty.is_none_or(|q| !q.fract().is_zero()) {
+      return invalid("advanced order classes require a whole-share --qty");
+    }
+
+    if !matches!(self.time_in_force.as_str(), "day" | "gtc") {
+      return invalid("advanced order classes only support day or gtc time in force");
+    }
+
+    match order_class {
+      "bracket" => {
+        if !has_take_profit || !has_stop_loss {
+          return invalid("bracket orders require both --take-profit and --stop-loss");
+        }
+        if !matches!(self.order_type.as_str(), "market" | "limit") {
+          return invalid("bracket entry orders must be market or limit");
+        }
+      }
+      "oco" => {
+        if !has_take_profit || !has_stop_loss {
+          return invalid("oco orders require both --take-profit and --stop-loss");
+        }
+        if self.order_type != "limit" {
+          return invalid("oco orders must use --type limit");
+        }
+      }
+      "oto" => {
+        if has_take_profit == has_stop_loss {
+          return invalid("oto orders require exactly one of --take-profit or --stop-loss");
+        }
+      }
+      other => {
+        return Err(AppError::InvalidOrder(format!("unknown order class '{other}'")));
+      }
+    }
+
+    // Bracket legs exit the entry side, while an OCO's own side is already the exit
+    if let (Some(tp), Some(sl)) = (&self.take_profit, &self.stop_loss) {
+      let long = (self.side == "buy") != (order_class == "oco");
+      let ordered = if long {
+        tp.limit_price > sl.stop_price
+      } else {
+        tp.limit_price < sl.stop_price
+      };
+      if !ordered {
+        return invalid("take-profit must be on the profitable side of the stop-loss");
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Trade {
+  #[serde(rename = "t")]
+  pub timestamp: DateTime<Utc>,
+  #[serde(rename = "p")]
+  pub price: Decimal,
+  #[serde(rename = "s")]
+  pub size: Decimal,
+  #[serde(rename = "x")]
+  pub exchange: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Quote {
+  #[serde(rename = "t")]
+  pub timestamp: DateTime<Utc>,
+  #[serde(rename = "bp")]
+  pub bid_price: Decimal,
+  #[serde(rename = "bs")]
+  pub bid_size: Decimal,
+  #[serde(rename = "ap")]
+  pub ask_price: Decimal,
+  #[serde(rename = "as")]
+  pub ask_size: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bar {
+  #[serde(rename = "t")]
+  pub timestamp: DateTime<Utc>,
+  #[serde(rename = "o")]
+  pub open: Decimal,
+  #[serde(rename = "h")]
+  pub high: Decimal,
+  #[serde(rename = "l")]
+  pub low: Decimal,
+  #[serde(rename = "c")]
+  pub close: Decimal,
+  #[serde(rename = "v")]
+  pub volume: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+  pub latest_trade: Option<Trade>,
+  pub latest_quote: Option<Quote>,
+  pub minute_bar: Option<Bar>,
+  pub daily_bar: Option<Bar>,
+  pub prev_daily_bar: Option<Bar>,
+}
+
+impl Snapshot {
+  /// Change of the last trade against the previous session's close, as (amount, percent).
+  pub fn day_change(&self) -> Option<(Decimal, Decimal)> {
+    let last = self.latest_trade.as_ref()?.price;
+    let prev_close = self.prev_daily_bar.as_ref()?.close;
+    if prev_close.is_zero() {
+      return None;
+    }
+
+    let change = last - prev_close;
+    Some((change, change / prev_close * Decimal::ONE_HUNDRED))
+  }
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorBody {
+  code: Option<u64>,
+  message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LatestTradeResponse {
+  trade: Trade,
+}
+
+#[derive(Debug, Deserialize)]
+struct LatestQuoteResponse {
+  quote: Quote,
+}
+
+#[derive(Debug, Deserialize)]
+struct LatestBarResponse {
+  bar: Bar,
+}
+
+// `bars` is null rather than empty when a page has no data
+#[derive(Debug, Deserialize)]
+struct BarsPage<T> {
+  bars: Option<T>,
+  next_page_token: Option<String>,
+}
+
+// Crypto endpoints are keyed by kind and then symbol, e.g. `{"quotes": {"BTC/USD": {...}}}`
+type CryptoDataResponse<T> = HashMap<String, HashMap<String, T>>;
+
 pub const SP500_STOCKS: [&str; 500] = [
   "MMM", "AOS", "ABT", "ABBV", "ACN", "ATVI", "ADM", "ADBE", "ADP", "AAP",
   "AES", "AFL", "A", "APD", "AKAM", "ALK", "ALB", "ARE", "ALGN", "ALLE",
@@ -99,34 +632,73 @@ pub const SP500_STOCKS: [&str; 500] = [
   "WTW", "GWW", "WYNN", "XEL", "XYL", "YUM", "ZBRA", "ZBH", "ZION", "ZTS"
 ];
 
+async fn parse_response<T>(response: reqwest::Response) -> AppResult<T>
+where
+  T: serde::de::DeserializeOwned,
+{
+  let status = response.status();
+  if status.is_success() {
+    return Ok(response.json().await?);
+  }
+
+  let body = response.text().await?;
+  Err(api_error(status.as_u16(), &body))
+}
+
+fn api_error(status: u16, body: &str) -> AppError {
+  match serde_json::from_str::<ApiErrorBody>(body) {
+    Ok(err) => AppError::AlpacaApi {
+      status,
+      code: err.code,
+      message: err.message,
+    },
+    Err(_) => AppError::AlpacaApi {
+      status,
+      code: None,
+      message: if body.trim().is_empty() {
+        "no error details returned".to_string()
+      } else {
+        body.trim().to_string()
+      },
+    },
+  }
+}
+
 impl AlpacaClient {
-  pub fn new(api_key: String, api_secret: String) -> Self {
-    let url = "https://api.alpaca.markets";
+  pub fn new(api_key: String, api_secret: String, environment: Environment) -> Self {
     Self {
       api_key,
       api_secret,
-      base_url: url.to_string(),
+      base_url: environment.base_url().to_string(),
+      data_url: DATA_URL.to_string(),
       client: Client::new(),
       base_stocks: SP500_STOCKS,
     }
   }
 
   async fn get_request<T>(&self, url: &str) -> AppResult<T>
+  where
+    T: serde::de::DeserializeOwned,
+  {
+    self.get_query_request(url, &[]).await
+  }
+
+  /// Like `get_request`, but URL-encodes the query parameters (page tokens are base64).
+  async fn get_query_request<T>(&self, url: &str, query: &[(&str, String)]) -> AppResult<T>
   where
     T: serde::de::DeserializeOwned,
   {
     let response = self
       .client
       .get(url)
+      .query(query)
       .header("Accept", "application/json")
       .header("APCA-API-KEY-ID", &self.api_key)
       .header("APCA-API-SECRET-KEY", &self.api_secret)
       .send()
-      .await?
-      .json()
       .await?;
 
-    Ok(response)
+    parse_response(response).await
   }
 
   async fn post_request<T>(&self, url: &str, body: serde_json::Value) -> AppResult<T>
@@ -141,11 +713,26 @@ impl AlpacaClient {
       .header("APCA-API-SECRET-KEY", &self.api_secret)
       .json(&body)
       .send()
-      .await?
-      .json()
       .await?;
 
-    Ok(response)
+    parse_response(response).await
+  }
+
+  async fn patch_request<T>(&self, url: &str, body: serde_json::Value) -> AppResult<T>
+  where
+    T: serde::de::DeserializeOwned,
+  {
+    let response = self
+      .client
+      .patch(url)
+      .header("Accept", "application/json")
+      .header("APCA-API-KEY-ID", &self.api_key)
+      .header("APCA-API-SECRET-KEY", &self.api_secret)
+      .json(&body)
+      .send()
+      .await?;
+
+    parse_response(response).await
   }
 
   async fn delete_request(&self, url: &str) -> AppResult<serde_json::Value> {
@@ -162,45 +749,441 @@ impl AlpacaClient {
       return Ok(serde_json::json!({}));
     }
 
-    let json = response.json().await?;
-    Ok(json)
+    parse_response(response).await
+  }
+
+  pub async fn fetch_account(&self) -> AppResult<Account> {
+    let url = format!("{}/v2/account", &self.base_url);
+    self.get_request(&url).await
   }
 
   pub async fn fetch_asset(&self, symbol: &str) -> AppResult<Asset> {
-    let url = format!("{}/v2/assets/{}", &self.base_url, symbol);
+    let url = format!("{}/v2/assets/{}", &self.base_url, path_symbol(symbol));
     self.get_request(&url).await
   }
 
+  async fn fetch_crypto_data<T>(&self, kind: &str, path: &str, symbol: &str) -> AppResult<T>
+  where
+    T: serde::de::DeserializeOwned,
+  {
+    let url = format!("{}{}/{}?symbols={}", &self.data_url, CRYPTO_DATA_PATH, path, symbol);
+    let mut response: CryptoDataResponse<T> = self.get_request(&url).await?;
+    response
+      .remove(kind)
+      .and_then(|mut by_symbol| by_symbol.remove(symbol))
+      .ok_or_else(|| AppError::Api(format!("No {kind} returned for {symbol}")))
+  }
+
+  #[allow(dead_code)]
+  pub async fn fetch_latest_trade(&self, symbol: &str) -> AppResult<Trade> {
+    if is_crypto(symbol) {
+      return self.fetch_crypto_data("trades", "latest/trades", symbol).await;
+    }
+
+    let url = format!("{}/v2/stocks/{}/trades/latest", &self.data_url, symbol);
+    let response: LatestTradeResponse = self.get_request(&url).await?;
+    Ok(response.trade)
+  }
+
+  #[allow(dead_code)]
+  pub async fn fetch_latest_quote(&self, symbol: &str) -> AppResult<Quote> {
+    if is_crypto(symbol) {
+      return self.fetch_crypto_data("quotes", "latest/quotes", symbol).await;
+    }
+
+    let url = format!("{}/v2/stocks/{}/quotes/latest", &self.data_url, symbol);
+    let response: LatestQuoteResponse = self.get_request(&url).await?;
+    Ok(response.quote)
+  }
+
+  #[allow(dead_code)]
+  pub async fn fetch_latest_bar(&self, symbol: &str) -> AppResult<Bar> {
+    if is_crypto(symbol) {
+      return self.fetch_crypto_data("bars", "latest/bars", symbol).await;
+    }
+
+    let url = format!("{}/v2/stocks/{}/bars/latest", &self.data_url, symbol);
+    let response: LatestBarResponse = self.get_request(&url).await?;
+    Ok(response.bar)
+  }
+
+  /// Stock snapshots come from `feed` when given, otherwise from the plan's default feed.
+  pub async fn fetch_snapshot(&self, symbol: &str, feed: Option<Feed>) -> AppResult<Snapshot> {
+    if is_crypto(symbol) {
+      return self.fetch_crypto_data("snapshots", "snapshots", symbol).await;
+    }
+
+    let url = format!("{}/v2/stocks/{}/snapshot", &self.data_url, symbol);
+    match feed {
+      Some(feed) => self.get_query_request(&url, &[("feed", feed.rest_param().to_string())]).await,
+      None => self.get_request(&url).await,
+    }
+  }
+
+  /// Historical bars between `start` and `end` (now if omitted), following `next_page_token` until
+  /// every page has been fetched.
+  pub async fn fetch_bars(
+    &self,
+    symbol: &str,
+    timeframe: &str,
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+    options: BarsOptions<'_>,
+  ) -> AppResult<Vec<Bar>> {
+    let mut query = vec![
+      ("timeframe", timeframe.to_string()),
+      ("start", start.to_rfc3339_opts(SecondsFormat::Secs, true)),
+      ("limit", "10000".to_string()),
+    ];
+    if let Some(end) = end {
+      query.push(("end", end.to_rfc3339_opts(SecondsFormat::Secs, true)));
+    }
+
+    let url = if is_crypto(symbol) {
+      query.push(("symbols", symbol.to_string()));
+      format!("{}{}/bars", &self.data_url, CRYPTO_DATA_PATH)
+    } else {
+      query.push(("adjustment", options.adjustment.unwrap_or("raw").to_string()));
+      query.push(("feed", options.feed.rest_param().to_string()));
+      format!("{}/v2/stocks/{}/bars", &self.data_url, symbol)
+    };
+
+    let mut bars = Vec::new();
+    loop {
+      let next_page_token = if is_crypto(symbol) {
+        let page: BarsPage<HashMap<String, Vec<Bar>>> = self.get_query_request(&url, &query).await?;
+        bars.extend(page.bars.and_then(|mut by_symbol| by_symbol.remove(symbol)).unwrap_or_default());
+        page.next_page_token
+      } else {
+        let page: BarsPage<Vec<Bar>> = self.get_query_request(&url, &query).await?;
+        bars.extend(page.bars.unwrap_or_default());
+        page.next_page_token
+      };
+
+      match next_page_token {
+        Some(token) if !token.is_empty() => {
+          query.retain(|(key, _)| *key != "page_token");
+          query.push(("page_token", token));
+        }
+        _ => return Ok(bars),
+      }
+    }
+  }
+
   pub async fn fetch_positions(&self) -> AppResult<Vec<Position>> {
     let url = format!("{}/v2/positions", &self.base_url);
     self.get_request(&url).await
   }
 
   pub async fn fetch_positions_by_symbol(&self, symbol: String) -> AppResult<Position> {
-    let url = format!("{}/v2/positions/{}", &self.base_url, symbol);
+    let url = format!("{}/v2/positions/{}", &self.base_url, path_symbol(&symbol));
     self.get_request(&url).await
   }
 
   pub async fn fetch_orders(&self, status: String) -> AppResult<Vec<Order>> {
-    let url = format!("{}/v2/orders?status={}", &self.base_url, status);
+    let url = format!("{}/v2/orders?status={}&nested=true", &self.base_url, status);
     self.get_request(&url).await
   }
 
-  pub async fn create_order(&self, side: String, symbol: String, notional: f64) -> AppResult<Order> {
+  pub async fn create_order(&self, order: &OrderRequest) -> AppResult<Order> {
+    order.validate()?;
+
     let url = format!("{}/v2/orders", &self.base_url);
-    let body = json!({
-      "symbol": symbol,
-      "notional": notional,
-      "side": side,
-      "type": "market",
-      "time_in_force": "day"
-    });
+    let body = serde_json::to_value(order)?;
 
     self.post_request(&url, body).await
   }
 
+  pub async fn cancel_all_orders(&self) -> AppResult<Value> {
+    let url = format!("{}/v2/orders", &self.base_url);
+    self.delete_request(&url).await
+  }
+
+  pub async fn close_position(&self, symbol: &str, percentage: Option<Decimal>) -> AppResult<Order> {
+    let url = match percentage {
+      Some(pct) => format!("{}/v2/positions/{}?percentage={}", &self.base_url, path_symbol(symbol), pct),
+      None => format!("{}/v2/positions/{}", &self.base_url, path_symbol(symbol)),
+    };
+    let json = self.delete_request(&url).await?;
+    Ok(serde_json::from_value(json)?)
+  }
+
+  pub async fn close_all_positions(&self, cancel_orders: bool) -> AppResult<Value> {
+    let url = format!("{}/v2/positions?cancel_orders={}", &self.base_url, cancel_orders);
+    self.delete_request(&url).await
+  }
+
+  pub async fn fetch_order(&self, order_id: &str) -> AppResult<Order> {
+    let url = format!("{}/v2/orders/{}", &self.base_url, order_id);
+    self.get_request(&url).await
+  }
+
+  pub async fn replace_order(&self, order_id: &str, replace: &ReplaceOrderRequest) -> AppResult<Order> {
+    replace.validate()?;
+
+    let url = format!("{}/v2/orders/{}", &self.base_url, order_id);
+    let body = serde_json::to_value(replace)?;
+
+    self.patch_request(&url, body).await
+  }
+
   pub async fn cancel_order(&self, order_id: String) -> AppResult<Value> {
     let url = format!("{}/v2/orders/{}", &self.base_url, order_id);
     self.delete_request(&url).await
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rust_decimal_macros::dec;
+
+  fn bar(close: Decimal) -> Bar {
+    Bar {
+      timestamp: DateTime::UNIX_EPOCH,
+      open: close,
+      high: close,
+      low: close,
+      close,
+      volume: Decimal::ZERO,
+    }
+  }
+
+  fn trade(price: Decimal) -> Trade {
+    Trade {
+      timestamp: DateTime::UNIX_EPOCH,
+      price,
+      size: Decimal::ONE,
+      exchange: None,
+    }
+  }
+
+  #[test]
+  fn test_api_error_parses_alpaca_body() {
+    let err = api_error(403, r#"{"code": 40310000, "message": "insufficient buying power"}"#);
+    assert_eq!(
+      err.to_string(),
+      "Alpaca rejected the request (HTTP 403, code 40310000): insufficient buying power"
+    );
+  }
+
+  #[test]
+  fn test_api_error_falls_back_to_raw_body() {
+    let err = api_error(502, "Bad Gateway");
+    assert_eq!(err.to_string(), "Alpaca rejected the request (HTTP 502): Bad Gateway");
+  }
+
+  fn order(order_type: &str) -> OrderRequest {
+    OrderRequest {
+      symbol: "AAPL".to_string(),
+      side: "buy".to_string(),
+      order_type: order_type.to_string(),
+      time_in_force: "day".to_string(),
+      notional: Some(dec!(100.0)),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_validate_market_order() {
+    assert!(OrderRequest::market("buy", "AAPL", dec!(100)).validate().is_ok());
+  }
+
+  #[test]
+  fn test_order_request_serializes_exact_decimals() {
+    let body = serde_json::to_value(OrderRequest::market("buy", "AAPL", dec!(100.10))).unwrap();
+    assert_eq!(body["notional"], "100.10");
+    assert!(body.get("qty").is_none());
+  }
+
+  #[test]
+  fn test_validate_limit_requires_limit_price() {
+    assert!(order("limit").validate().is_err());
+
+    let mut limit = order("limit");
+    limit.limit_price = Some(dec!(150.0));
+    assert!(limit.validate().is_ok());
+  }
+
+  #[test]
+  fn test_validate_stop_limit_requires_both_prices() {
+    let mut stop_limit = order("stop_limit");
+    stop_limit.stop_price = Some(dec!(145.0));
+    assert!(stop_limit.validate().is_err());
+
+    stop_limit.limit_price = Some(dec!(144.0));
+    assert!(stop_limit.validate().is_ok());
+  }
+
+  #[test]
+  fn test_validate_trailing_stop_requires_single_trail() {
+    let mut trailing = order("trailing_stop");
+    assert!(trailing.validate().is_err());
+
+    trailing.trail_percent = Some(dec!(2.5));
+    assert!(trailing.validate().is_ok());
+
+    trailing.trail_price = Some(dec!(1.0));
+    assert!(trailing.validate().is_err());
+  }
+
+  #[test]
+  fn test_validate_rejects_non_positive_prices() {
+    let mut limit = order("limit");
+    limit.limit_price = Some(dec!(0.0));
+    assert!(limit.validate().is_err());
+  }
+
+  #[test]
+  fn test_validate_notional_requires_day() {
+    let mut market = order("market");
+    market.time_in_force = "gtc".to_string();
+    assert!(market.validate().is_err());
+  }
+
+  #[test]
+  fn test_validate_qty_and_notional_are_exclusive() {
+    let mut both = order("market");
+    both.qty = Some(dec!(10.0));
+    assert!(both.validate().is_err());
+
+    both.notional = None;
+    assert!(both.validate().is_ok());
+  }
+
+  #[test]
+  fn test_validate_fractional_qty_requires_day() {
+    let mut fractional = order("limit");
+    fractional.notional = None;
+    fractional.qty = Some(dec!(0.5));
+    fractional.limit_price = Some(dec!(150.0));
+    fractional.time_in_force = "gtc".to_string();
+    assert!(fractional.validate().is_err());
+
+    fractional.qty = Some(dec!(5.0));
+    assert!(fractional.validate().is_ok());
+  }
+
+  #[test]
+  fn test_validate_replace_order() {
+    assert!(ReplaceOrderRequest::default().validate().is_err());
+
+    let mut replace = ReplaceOrderRequest {
+      limit_price: Some(dec!(150.0)),
+      ..Default::default()
+    };
+    assert!(replace.validate().is_ok());
+
+    replace.qty = Some(dec!(0));
+    assert!(replace.validate().is_err());
+
+    let tif_only = ReplaceOrderRequest {
+      time_in_force: Some("gtc".to_string()),
+      ..Default::default()
+    };
+    assert!(tif_only.validate().is_ok());
+  }
+
+  fn bracket() -> OrderRequest {
+    let mut bracket = order("market");
+    bracket.notional = None;
+    bracket.qty = Some(dec!(10.0));
+    bracket.order_class = Some("bracket".to_string());
+    bracket.take_profit = Some(TakeProfit { limit_price: dec!(200.0) });
+    bracket.stop_loss = Some(StopLoss {
+      stop_price: dec!(180.0),
+      limit_price: None,
+    });
+    bracket
+  }
+
+  #[test]
+  fn test_validate_bracket_order() {
+    assert!(bracket().validate().is_ok());
+
+    let mut missing_leg = bracket();
+    missing_leg.stop_loss = None;
+    assert!(missing_leg.validate().is_err());
+  }
+
+  #[test]
+  fn test_validate_bracket_rejects_notional() {
+    let mut notional = bracket();
+    notional.qty = None;
+    notional.notional = Some(dec!(1000.0));
+    assert!(notional.validate().is_err());
+  }
+
+  #[test]
+  fn test_validate_bracket_leg_ordering() {
+    let mut sell = bracket();
+    sell.side = "sell".to_string();
+    assert!(sell.validate().is_err());
+  }
+
+  #[test]
+  fn test_validate_oco_takes_limit_from_take_profit() {
+    let mut oco = bracket();
+    oco.side = "sell".to_string();
+    oco.order_type = "limit".to_string();
+    oco.order_class = Some("oco".to_string());
+    assert!(oco.validate().is_ok());
+  }
+
+  #[test]
+  fn test_day_change_against_previous_close() {
+    let snapshot = Snapshot {
+      latest_trade: Some(trade(dec!(110.0))),
+      latest_quote: None,
+      minute_bar: None,
+      daily_bar: None,
+      prev_daily_bar: Some(bar(dec!(100.0))),
+    };
+
+    assert_eq!(snapshot.day_change(), Some((dec!(10), dec!(10))));
+  }
+
+  #[test]
+  fn test_day_change_without_previous_close() {
+    let snapshot = Snapshot {
+      latest_trade: Some(trade(dec!(110.0))),
+      latest_quote: None,
+      minute_bar: None,
+      daily_bar: None,
+      prev_daily_bar: None,
+    };
+
+    assert_eq!(snapshot.day_change(), None);
+  }
+
+  #[test]
+  fn test_feed_urls_and_config_names() {
+    assert_eq!(Feed::DelayedSip.stream_url(), "wss://stream.data.alpaca.markets/v2/delayed_sip");
+    assert_eq!(Feed::from_arg("test"), Feed::Test);
+    assert_eq!(serde_json::to_string(&Feed::DelayedSip).unwrap(), "\"delayed_sip\"");
+  }
+
+  #[test]
+  fn test_normalize_crypto_symbols() {
+    assert_eq!(normalize_symbol("btc-usd"), "BTC/USD");
+    assert_eq!(normalize_symbol("eth/usd"), "ETH/USD");
+    assert_eq!(normalize_symbol("aapl"), "AAPL");
+    assert_eq!(normalize_symbol("eth-btc"), "ETH/BTC");
+    assert_eq!(normalize_symbol("brk-b"), "BRK-B");
+    assert!(!is_crypto(&normalize_symbol("brk-b")));
+    assert_eq!(path_symbol("BTC/USD"), "BTCUSD");
+  }
+
+  #[test]
+  fn test_validate_crypto_orders() {
+    let mut order = OrderRequest::market("buy", "BTC/USD", dec!(25));
+    assert!(order.validate().is_err(), "day is not valid for crypto");
+
+    order.time_in_force = "gtc".to_string();
+    assert!(order.validate().is_ok());
+
+    order.order_type = "trailing_stop".to_string();
+    order.trail_percent = Some(dec!(1));
+    assert!(order.validate().is_err());
+  }
+}
@@ -1,10 +1,34 @@
+use chrono::{DateTime, Utc};
+use clap::parser::ValueSource;
 use clap::ArgMatches;
-
-use crate::alpaca_api::AlpacaClient;
-use crate::credentials::{write_credentials, Credentials};
+use rust_decimal::Decimal;
+use serde_json::Value;
+
+use crate::alpaca_api::{
+  is_crypto, normalize_symbol, AlpacaClient, BarsOptions, Environment, Feed, Order, OrderRequest,
+  ReplaceOrderRequest, StopLoss, TakeProfit,
+};
+use crate::cli::output::{
+  account_table, auth_status_table, auth_verification_table, bars_table, fill_table, orders_table,
+  positions_table, prices_table, render, render_json_lines, summary_table, trade_update_line,
+  AuthStatus, AuthVerification, BarRow, OutputFormat, PriceRow, Table,
+};
+use crate::cli::utils::confirm;
+use crate::config::{read_config, resolve_environment, resolve_feed, write_config};
+use crate::credentials::{
+  credentials_from_env, decrypt_credentials, encrypt_credentials, get_credentials,
+  get_credentials_path, is_encrypted, mask_key, remove_credentials, write_credentials, Credentials,
+};
 use crate::error::{AppError, AppResult};
-
-pub fn handle_auth(auth_args: &ArgMatches) -> AppResult<()> {
+use crate::portfolio::{summarize, SortKey};
+use crate::trade_updates::TradeUpdateStream;
+
+pub async fn handle_auth(
+  auth_args: &ArgMatches,
+  profile: &str,
+  paper: Option<bool>,
+  output: OutputFormat,
+) -> AppResult<()> {
   if let Some(set_args) = auth_args.subcommand_matches("set") {
     let credentials = Credentials {
       apca_api_key: set_args
@@ -15,72 +39,313 @@ pub fn handle_auth(auth_args: &ArgMatches) -> AppResult<()> {
         .get_one::<String>("secret-key")
         .cloned()
         .unwrap_or_default(),
+      paper,
     };
-    write_credentials(&credentials)?;
+    write_credentials(profile, &credentials)?;
+    eprintln!("Saved credentials for profile '{}'", profile);
+    return Ok(());
+  }
+
+  if auth_args.subcommand_matches("encrypt").is_some() {
+    encrypt_credentials()?;
+    eprintln!("Credentials encrypted. Set STOCK_TRADER_PASSPHRASE to unlock them for a shell session");
+    return Ok(());
+  }
+
+  if auth_args.subcommand_matches("decrypt").is_some() {
+    decrypt_credentials()?;
+    eprintln!("Credentials decrypted");
     return Ok(());
   }
 
   if auth_args.subcommand_matches("reset").is_some() {
-    let empty_credentials = Credentials {
-      apca_api_key: String::new(),
-      apca_secret_key: String::new(),
+    if remove_credentials(profile)? {
+      eprintln!("Removed credentials for profile '{}'", profile);
+    } else {
+      eprintln!("No credentials stored for profile '{}'", profile);
+    }
+    return Ok(());
+  }
+
+  if auth_args.subcommand_matches("status").is_some() {
+    let from_env = credentials_from_env().is_some();
+    let credentials = get_credentials(profile)?;
+    let environment = resolve_environment(paper, credentials.paper)?;
+    let status = AuthStatus {
+      profile: profile.to_string(),
+      source: if from_env { "environment".to_string() } else { "file".to_string() },
+      api_key: mask_key(&credentials.apca_api_key),
+      environment: environment.label().to_string(),
+      file: get_credentials_path()?.display().to_string(),
+      encrypted: is_encrypted()?,
     };
-    write_credentials(&empty_credentials)?;
+    return render(output, &status, &auth_status_table(&status));
+  }
+
+  if auth_args.subcommand_matches("verify").is_some() {
+    let credentials = get_credentials(profile)?;
+    let configured = resolve_environment(paper, credentials.paper)?;
+    return verify_credentials(profile, &credentials, configured, output).await;
+  }
+
+  Ok(())
+}
+
+/// Live and paper keys are distinct, so try both to find out which environment the keys belong to.
+async fn verify_credentials(
+  profile: &str,
+  credentials: &Credentials,
+  configured: Environment,
+  output: OutputFormat,
+) -> AppResult<()> {
+  let client = |environment| {
+    AlpacaClient::new(
+      credentials.apca_api_key.clone(),
+      credentials.apca_secret_key.clone(),
+      environment,
+    )
+  };
+  let live = client(Environment::Live);
+  let paper = client(Environment::Paper);
+  let (live_result, paper_result) =
+    futures::future::join(live.fetch_account(), paper.fetch_account()).await;
+
+  let (environment, account) = match (live_result, paper_result) {
+    (Ok(account), _) => (Environment::Live, account),
+    (_, Ok(account)) => (Environment::Paper, account),
+    (Err(live_err), Err(paper_err)) => {
+      let err = if configured == Environment::Live { live_err } else { paper_err };
+      return Err(AppError::Config(format!(
+        "Credentials for profile '{profile}' did not work against LIVE or PAPER: {err}"
+      )));
+    }
+  };
+
+  if environment != configured {
+    eprintln!(
+      "Warning: profile '{}' is set up for {} but its keys belong to {}",
+      profile,
+      configured.label(),
+      environment.label()
+    );
+  }
+
+  let verification = AuthVerification {
+    profile: profile.to_string(),
+    api_key: mask_key(&credentials.apca_api_key),
+    environment: environment.label().to_string(),
+    account_number: account.account_number,
+    account_status: account.status,
+  };
+  render(output, &verification, &auth_verification_table(&verification))
+}
+
+pub fn handle_config(config_args: &ArgMatches) -> AppResult<()> {
+  if let Some(set_args) = config_args.subcommand_matches("set") {
+    let mut config = read_config()?;
+    if let Some(paper) = set_args.get_one::<bool>("paper") {
+      config.paper = *paper;
+    }
+    if let Some(feed) = set_args.get_one::<String>("feed") {
+      config.feed = Feed::from_arg(feed);
+    }
+    write_config(&config)?;
+    println!("{}", serde_json::to_string_pretty(&config)?);
+    return Ok(());
+  }
+
+  if config_args.subcommand_matches("show").is_some() {
+    let config = read_config()?;
+    println!("{}", serde_json::to_string_pretty(&config)?);
     return Ok(());
   }
 
   Ok(())
 }
 
-pub async fn handle_prices(prices_args: &ArgMatches, api_key: &str, api_secret: &str) -> AppResult<()> {
-  let client = AlpacaClient::new(api_key.to_string(), api_secret.to_string());
+fn print_environment_banner(environment: Environment) {
+  eprintln!(
+    "[{}] Trading against {}",
+    environment.label(),
+    environment.base_url()
+  );
+}
+
+fn order_diff_table(old: &Order, new: &Order) -> Table {
+  let text = |v: Option<&String>| v.cloned();
+  let decimal = |v: Option<Decimal>| v.map(|d| d.normalize().to_string());
+  let fields = [
+    ("id", Some(old.id.clone()), Some(new.id.clone())),
+    ("qty", decimal(old.qty), decimal(new.qty)),
+    ("limit_price", decimal(old.limit_price), decimal(new.limit_price)),
+    ("stop_price", decimal(old.stop_price), decimal(new.stop_price)),
+    ("trail_price", decimal(old.trail_price), decimal(new.trail_price)),
+    ("trail_percent", decimal(old.trail_percent), decimal(new.trail_percent)),
+    ("time_in_force", text(old.time_in_force.as_ref()), text(new.time_in_force.as_ref())),
+    ("status", Some(old.status.clone()), Some(new.status.clone())),
+  ];
+
+  let mut table = Table::new(&["Field", "Before", "After"]);
+  for (name, before, after) in fields {
+    if before == after {
+      continue;
+    }
+    let show = |v: Option<String>| v.unwrap_or_else(|| "-".to_string());
+    table.add_row(vec![name.to_string(), show(before), show(after)]);
+  }
+
+  table
+}
+
+pub async fn handle_account(
+  api_key: &str,
+  api_secret: &str,
+  environment: Environment,
+  output: OutputFormat,
+) -> AppResult<()> {
+  let client = AlpacaClient::new(api_key.to_string(), api_secret.to_string(), environment);
+  let account = client.fetch_account().await?;
+
+  render(output, &account, &account_table(&account))
+}
+
+pub async fn handle_prices(
+  prices_args: &ArgMatches,
+  api_key: &str,
+  api_secret: &str,
+  environment: Environment,
+  output: OutputFormat,
+) -> AppResult<()> {
+  let client = AlpacaClient::new(api_key.to_string(), api_secret.to_string(), environment);
 
   if let Some(symbols) = prices_args.get_many::<String>("symbols") {
-    let symbols: Vec<String> = symbols.map(|s| s.to_uppercase()).collect();
+    let symbols: Vec<String> = symbols.map(|s| normalize_symbol(s)).collect();
 
-    println!("Fetching {} symbols concurrently...\n", symbols.len());
+    eprintln!("Fetching {} symbols concurrently...", symbols.len());
 
     let futures: Vec<_> = symbols
       .iter()
-      .map(|symbol| client.fetch_asset(symbol))
+      .map(|symbol| futures::future::join(client.fetch_asset(symbol), client.fetch_snapshot(symbol, None)))
       .collect();
 
     let results = futures::future::join_all(futures).await;
 
-    for (symbol, result) in symbols.iter().zip(results) {
+    let mut rows = Vec::new();
+    for (symbol, (asset, result)) in symbols.into_iter().zip(results) {
       match result {
-        Ok(asset) => {
-          println!("{}:", symbol);
-          println!("  Name: {}", asset.name);
-          println!("  Exchange: {}", asset.exchange);
-          println!("  Tradable: {}", asset.tradable);
-          println!();
-        }
+        Ok(snapshot) => rows.push(PriceRow::new(symbol, asset.ok(), snapshot)),
         Err(e) => {
-          println!("{}: Error - {}\n", symbol, e);
+          eprintln!("{}: Error - {}", symbol, e);
         }
       }
     }
 
-    return Ok(());
+    return render(output, &rows, &prices_table(&rows));
   }
 
   if let Some(symbol) = prices_args.get_one::<String>("symbol") {
-    let asset = client.fetch_asset(&symbol.to_uppercase()).await?;
-    println!("{}", serde_json::to_string_pretty(&asset)?);
-    return Ok(());
+    let symbol = normalize_symbol(symbol);
+    let (asset, snapshot) =
+      futures::future::join(client.fetch_asset(&symbol), client.fetch_snapshot(&symbol, None)).await;
+    let row = PriceRow::new(symbol, asset.ok(), snapshot?);
+    return render(output, &row, &prices_table(std::slice::from_ref(&row)));
   }
 
   Err(AppError::MissingArgument("symbol or symbols".into()))
 }
 
-pub async fn handle_positions(positions_args: &ArgMatches, api_key: &str, api_secret: &str) -> AppResult<()> {
-  let client = AlpacaClient::new(api_key.to_string(), api_secret.to_string());
+pub async fn handle_bars(
+  bars_args: &ArgMatches,
+  api_key: &str,
+  api_secret: &str,
+  environment: Environment,
+  output: OutputFormat,
+) -> AppResult<()> {
+  let client = AlpacaClient::new(api_key.to_string(), api_secret.to_string(), environment);
+
+  let symbols: Vec<String> = match (
+    bars_args.get_many::<String>("symbols"),
+    bars_args.get_one::<String>("symbol"),
+  ) {
+    (Some(symbols), _) => symbols.map(|s| normalize_symbol(s)).collect(),
+    (None, Some(symbol)) => vec![normalize_symbol(symbol)],
+    (None, None) => return Err(AppError::MissingArgument("symbol".into())),
+  };
+  let timeframe = bars_args
+    .get_one::<String>("timeframe")
+    .ok_or_else(|| AppError::MissingArgument("timeframe".into()))?;
+  let start = *bars_args
+    .get_one::<DateTime<Utc>>("start")
+    .ok_or_else(|| AppError::MissingArgument("start".into()))?;
+  let end = bars_args.get_one::<DateTime<Utc>>("end").copied();
+  let options = BarsOptions {
+    adjustment: bars_args.get_one::<String>("adjustment").map(|s| s.as_str()),
+    feed: resolve_feed(bars_args.get_one::<String>("feed"))?,
+  };
+
+  let futures = symbols
+    .iter()
+    .map(|symbol| client.fetch_bars(symbol, timeframe, start, end, options));
+  let results = futures::future::join_all(futures).await;
+
+  let mut rows = Vec::new();
+  for (symbol, bars) in symbols.iter().zip(results) {
+    rows.extend(bars?.into_iter().map(|bar| BarRow::new(symbol, bar)));
+  }
+
+  let out = bars_args.get_one::<String>("out");
+  // A file gets CSV, or JSON lines for a .json/.jsonl name, unless -o was given explicitly
+  let output = match out {
+    Some(path) if bars_args.value_source("output") != Some(ValueSource::CommandLine) => {
+      if path.ends_with(".json") || path.ends_with(".jsonl") || path.ends_with(".ndjson") {
+        OutputFormat::Json
+      } else {
+        OutputFormat::Csv
+      }
+    }
+    _ => output,
+  };
+
+  let text = match output {
+    OutputFormat::Json => render_json_lines(&rows)?,
+    OutputFormat::Csv => bars_table(&rows).render_csv(),
+    OutputFormat::Table => bars_table(&rows).render_table(),
+  };
+  match out {
+    Some(path) => {
+      std::fs::write(path, text)?;
+      eprintln!("Wrote {} bars to {}", rows.len(), path);
+    }
+    None => print!("{}", text),
+  }
+
+  Ok(())
+}
+
+pub async fn handle_positions(
+  positions_args: &ArgMatches,
+  api_key: &str,
+  api_secret: &str,
+  environment: Environment,
+  output: OutputFormat,
+) -> AppResult<()> {
+  let client = AlpacaClient::new(api_key.to_string(), api_secret.to_string(), environment);
+
+  if let Some(close_args) = positions_args.subcommand_matches("close") {
+    print_environment_banner(environment);
+    return close_positions(&client, close_args, output).await;
+  }
+
+  let sort = SortKey::from_arg(
+    positions_args
+      .get_one::<String>("sort")
+      .map_or("symbol", |s| s.as_str()),
+  );
 
-  if let Some(symbols) = positions_args.get_many::<String>("symbols") {
-    let symbols: Vec<String> = symbols.map(|s| s.to_uppercase()).collect();
+  let positions = if let Some(symbols) = positions_args.get_many::<String>("symbols") {
+    let symbols: Vec<String> = symbols.map(|s| normalize_symbol(s)).collect();
 
-    println!("Fetching {} symbols concurrently...\n", symbols.len());
+    eprintln!("Fetching {} symbols concurrently...", symbols.len());
 
     let futures: Vec<_> = symbols
       .iter()
@@ -89,47 +354,197 @@ pub async fn handle_positions(positions_args: &ArgMatches, api_key: &str, api_se
 
     let results = futures::future::join_all(futures).await;
 
+    let mut positions = Vec::new();
     for (symbol, result) in symbols.iter().zip(results) {
       match result {
-        Ok(position) => {
-          println!("Symbol: {}", position.symbol);
-          println!("Current price: {}", position.current_price);
-          println!("Qty: {}", position.qty);
-        }
+        Ok(position) => positions.push(position),
         Err(e) => {
-          println!("{}: Error - {}\n", symbol, e)
+          eprintln!("{}: Error - {}", symbol, e)
         }
       }
     }
+    positions
+  } else if let Some(s) = positions_args.get_one::<String>("symbol") {
+    vec![client.fetch_positions_by_symbol(normalize_symbol(s)).await?]
+  } else {
+    client.fetch_positions().await?
+  };
+
+  let summary = summarize(positions, sort);
+  if positions_args.get_flag("summary") {
+    return render(output, &summary, &summary_table(&summary));
+  }
+
+  render(output, &summary.positions, &positions_table(&summary.positions))
+}
+
+async fn close_positions(
+  client: &AlpacaClient,
+  close_args: &ArgMatches,
+  output: OutputFormat,
+) -> AppResult<()> {
+  let skip_confirm = close_args.get_flag("yes");
+
+  if close_args.get_flag("all") {
+    let cancel_orders = close_args.get_flag("cancel-orders");
+    let positions = client.fetch_positions().await?;
+    let open_orders = if cancel_orders {
+      client.fetch_orders("open".to_string()).await?
+    } else {
+      Vec::new()
+    };
+
+    if positions.is_empty() && open_orders.is_empty() {
+      eprintln!("No open positions to close");
+      return Ok(());
+    }
+
+    eprintln!("The following {} position(s) will be closed:", positions.len());
+    eprint!("{}", positions_table(&summarize(positions, SortKey::Symbol).positions).render_table());
+    if cancel_orders {
+      eprintln!("The following {} open order(s) will be cancelled:", open_orders.len());
+      eprint!("{}", orders_table(&open_orders).render_table());
+    }
+
+    if !skip_confirm && !confirm("Close all positions?")? {
+      eprintln!("Aborted");
+      return Ok(());
+    }
+
+    let results = client.close_all_positions(cancel_orders).await?;
+    return render(output, &results, &bulk_results_table("Symbol", "symbol", &results));
+  }
+
+  let symbol = close_args
+    .get_one::<String>("symbol")
+    .map(|s| normalize_symbol(s))
+    .ok_or_else(|| AppError::MissingArgument("symbol".into()))?;
+
+  let percentage = close_args.get_one::<Decimal>("percentage").copied();
+  if let Some(pct) = percentage {
+    if pct <= Decimal::ZERO || pct > Decimal::ONE_HUNDRED {
+      return Err(AppError::InvalidOrder("percentage must be between 0 and 100".into()));
+    }
+  }
+
+  let position = client.fetch_positions_by_symbol(symbol.clone()).await?;
+  eprintln!(
+    "Closing {}% of {}: qty {} (market value ${})",
+    percentage.unwrap_or(Decimal::ONE_HUNDRED),
+    position.symbol,
+    position.qty,
+    position.market_value
+  );
+
+  if !skip_confirm && !confirm(&format!("Close {} position?", symbol))? {
+    eprintln!("Aborted");
     return Ok(());
   }
 
-  match positions_args.get_one::<String>("symbol") {
-    Some(s) => {
-      let position = client.fetch_positions_by_symbol(s.to_uppercase()).await?;
-      println!("{}", serde_json::to_string_pretty(&position)?);
+  let order = client.close_position(&symbol, percentage).await?;
+  render(output, &order, &orders_table(std::slice::from_ref(&order)))
+}
+
+/// Summarises the per-item `{.., status}` array returned by bulk cancel/close endpoints.
+fn bulk_results_table(header: &str, key: &str, results: &Value) -> Table {
+  let mut table = Table::new(&[header, "Status"]);
+  for item in results.as_array().into_iter().flatten() {
+    let name = item.get(key).and_then(Value::as_str).unwrap_or("-");
+    let status = item.get("status").map(|s| s.to_string()).unwrap_or_else(|| "-".into());
+    table.add_row(vec![name.to_string(), status]);
+  }
+
+  table
+}
+
+/// Follows `trade_updates` until the order reaches a terminal state. The order is re-fetched after
+/// every (re)connect so events missed while disconnected can't leave us waiting forever.
+async fn wait_for_order(
+  client: &AlpacaClient,
+  api_key: &str,
+  api_secret: &str,
+  environment: Environment,
+  order_id: &str,
+) -> AppResult<Order> {
+  loop {
+    let mut stream = TradeUpdateStream::connect_with_retry(api_key, api_secret, environment).await?;
+
+    let order = client.fetch_order(order_id).await?;
+    if order.is_terminal() {
+      return Ok(order);
     }
-    None => {
-      let positions = client.fetch_positions().await?;
-      println!("{}", serde_json::to_string_pretty(&positions)?);
+
+    loop {
+      tokio::select! {
+        _ = tokio::signal::ctrl_c() => {
+          return Err(AppError::Api(format!("Stopped waiting; order {order_id} is still open")));
+        }
+
+        update = stream.next() => match update {
+          Ok(Some(update)) if update.order.id == order_id => {
+            eprintln!("{}", trade_update_line(&update));
+            if update.order.is_terminal() {
+              return Ok(update.order);
+            }
+          }
+          Ok(Some(_)) => {}
+          Ok(None) | Err(_) => {
+            eprintln!("Connection lost. Reconnecting...");
+            break;
+          }
+        }
+      }
     }
-  };
+  }
+}
 
-  Ok(())
+async fn watch_trade_updates(
+  api_key: &str,
+  api_secret: &str,
+  environment: Environment,
+  output: OutputFormat,
+) -> AppResult<()> {
+  eprintln!("Watching order updates (Ctrl+C to stop)...");
+
+  loop {
+    let mut stream = TradeUpdateStream::connect_with_retry(api_key, api_secret, environment).await?;
+
+    loop {
+      tokio::select! {
+        _ = tokio::signal::ctrl_c() => return Ok(()),
+
+        update = stream.next() => match update {
+          Ok(Some(update)) => match output {
+            OutputFormat::Json => println!("{}", serde_json::to_string(&update)?),
+            _ => println!("{}", trade_update_line(&update)),
+          },
+          Ok(None) | Err(_) => {
+            eprintln!("Connection lost. Reconnecting...");
+            break;
+          }
+        }
+      }
+    }
+  }
 }
 
-pub async fn handle_orders(orders_args: &ArgMatches, api_key: &str, api_secret: &str) -> AppResult<()> {
-  let client = AlpacaClient::new(api_key.to_string(), api_secret.to_string());
+pub async fn handle_orders(
+  orders_args: &ArgMatches,
+  api_key: &str,
+  api_secret: &str,
+  environment: Environment,
+  output: OutputFormat,
+) -> AppResult<()> {
+  let client = AlpacaClient::new(api_key.to_string(), api_secret.to_string(), environment);
+  print_environment_banner(environment);
 
   if let Some(list_args) = orders_args.subcommand_matches("list") {
     let status = list_args
       .get_one::<String>("status")
       .ok_or_else(|| AppError::MissingArgument("status".into()))?;
 
-    let json = client.fetch_orders(status.to_lowercase()).await?;
-    println!("{}", serde_json::to_string_pretty(&json)?);
-
-    return Ok(());
+    let orders = client.fetch_orders(status.to_lowercase()).await?;
+    return render(output, &orders, &orders_table(&orders));
   }
 
   if let Some(execute_args) = orders_args.subcommand_matches("execute") {
@@ -141,28 +556,120 @@ pub async fn handle_orders(orders_args: &ArgMatches, api_key: &str, api_secret:
       .get_one::<String>("symbol")
       .ok_or_else(|| AppError::MissingArgument("symbol".into()))?;
 
-    let notional = execute_args
-      .get_one::<f64>("notional")
-      .copied()
-      .unwrap_or(5.0);
+    let qty = execute_args.get_one::<Decimal>("qty").copied();
+    let notional = match qty {
+      Some(_) => None,
+      None => Some(
+        execute_args
+          .get_one::<Decimal>("notional")
+          .copied()
+          .unwrap_or(Decimal::new(5, 0)),
+      ),
+    };
 
-    let json = client.create_order(side.to_lowercase(), symbol.to_uppercase(), notional).await?;
-    println!("{}", serde_json::to_string_pretty(&json)?);
+    let order_type = execute_args
+      .get_one::<String>("type")
+      .ok_or_else(|| AppError::MissingArgument("type".into()))?;
+
+    let symbol = normalize_symbol(symbol);
+    let mut time_in_force = execute_args
+      .get_one::<String>("tif")
+      .ok_or_else(|| AppError::MissingArgument("tif".into()))?
+      .as_str();
+    // Crypto has no trading day, so default to gtc unless --tif was given
+    if is_crypto(&symbol) && execute_args.value_source("tif") == Some(ValueSource::DefaultValue) {
+      time_in_force = "gtc";
+    }
 
-    return Ok(());
+    let order = OrderRequest {
+      symbol,
+      side: side.to_lowercase(),
+      order_type: order_type.to_string(),
+      time_in_force: time_in_force.to_string(),
+      qty,
+      notional,
+      limit_price: execute_args.get_one::<Decimal>("limit-price").copied(),
+      stop_price: execute_args.get_one::<Decimal>("stop-price").copied(),
+      trail_price: execute_args.get_one::<Decimal>("trail-price").copied(),
+      trail_percent: execute_args.get_one::<Decimal>("trail-percent").copied(),
+      order_class: execute_args.get_one::<String>("class").cloned(),
+      take_profit: execute_args
+        .get_one::<Decimal>("take-profit")
+        .map(|&limit_price| TakeProfit { limit_price }),
+      stop_loss: execute_args
+        .get_one::<Decimal>("stop-loss")
+        .map(|&stop_price| StopLoss {
+          stop_price,
+          limit_price: execute_args.get_one::<Decimal>("stop-loss-limit").copied(),
+        }),
+    };
+
+    let order = client.create_order(&order).await?;
+    if !execute_args.get_flag("wait") {
+      return render(output, &order, &orders_table(std::slice::from_ref(&order)));
+    }
+
+    eprintln!("Submitted order {}. Waiting for it to complete (Ctrl+C to stop waiting)...", order.id);
+    let order = wait_for_order(&client, api_key, api_secret, environment, &order.id).await?;
+    return render(output, &order, &fill_table(&order));
+  }
+
+  if orders_args.subcommand_matches("watch").is_some() {
+    return watch_trade_updates(api_key, api_secret, environment, output).await;
   }
 
   if let Some(cancel_args) = orders_args.subcommand_matches("cancel") {
+    if cancel_args.get_flag("all") {
+      let open_orders = client.fetch_orders("open".to_string()).await?;
+      if open_orders.is_empty() {
+        eprintln!("No open orders to cancel");
+        return Ok(());
+      }
+
+      eprintln!("The following {} open order(s) will be cancelled:", open_orders.len());
+      eprint!("{}", orders_table(&open_orders).render_table());
+
+      if !cancel_args.get_flag("yes") && !confirm("Cancel all open orders?")? {
+        eprintln!("Aborted");
+        return Ok(());
+      }
+
+      let results = client.cancel_all_orders().await?;
+      return render(output, &results, &bulk_results_table("Order ID", "id", &results));
+    }
+
     let order_id = cancel_args
       .get_one::<String>("order_id")
       .ok_or_else(|| AppError::MissingArgument("order_id".into()))?;
 
-    let json = client.cancel_order(order_id.to_string()).await?;
-    println!("{}", serde_json::to_string_pretty(&json)?);
+    client.cancel_order(order_id.to_string()).await?;
+    eprintln!("Cancelled order {}", order_id);
 
     return Ok(());
   }
 
+  if let Some(replace_args) = orders_args.subcommand_matches("replace") {
+    let order_id = replace_args
+      .get_one::<String>("order_id")
+      .ok_or_else(|| AppError::MissingArgument("order_id".into()))?;
+
+    let replace = ReplaceOrderRequest {
+      qty: replace_args.get_one::<Decimal>("qty").copied(),
+      time_in_force: replace_args.get_one::<String>("tif").cloned(),
+      limit_price: replace_args.get_one::<Decimal>("limit-price").copied(),
+      stop_price: replace_args.get_one::<Decimal>("stop-price").copied(),
+      trail: replace_args.get_one::<Decimal>("trail").copied(),
+    };
+    // The client validates too, but fail before fetching the order being replaced
+    replace.validate()?;
+
+    let old = client.fetch_order(order_id).await?;
+    let new = client.replace_order(order_id, &replace).await?;
+    eprintln!("Replaced order {} ({} {} {})", old.id, old.side, old.symbol, old.order_type);
+
+    return render(output, &new, &order_diff_table(&old, &new));
+  }
+
   if let Some(pick_args) = orders_args.subcommand_matches("randombuy") {
     let positions = client.fetch_positions().await?;
 
@@ -178,7 +685,7 @@ pub async fn handle_orders(orders_args: &ArgMatches, api_key: &str, api_secret:
       .map(|&s| s.to_string())
       .collect();
 
-    println!("Picking from {} candidates...", candidates.len());
+    eprintln!("Picking from {} candidates...", candidates.len());
 
     let symbol = crate::cli::utils::select_random_stock(
       candidates,
@@ -187,16 +694,14 @@ pub async fn handle_orders(orders_args: &ArgMatches, api_key: &str, api_secret:
     .ok_or_else(|| AppError::Config("No stocks available to buy".into()))?;
 
     let notional = pick_args
-      .get_one::<f64>("notional")
+      .get_one::<Decimal>("notional")
       .copied()
-      .unwrap_or(5.0);
+      .unwrap_or(Decimal::new(5, 0));
 
-    println!("Picked {}. Executing order...", symbol);
+    eprintln!("Picked {}. Executing order...", symbol);
 
-    let order = client.create_order("buy".to_string(), symbol, notional).await?;
-    println!("{}", serde_json::to_string_pretty(&order)?);
-
-    return Ok(());
+    let order = client.create_order(&OrderRequest::market("buy", &symbol, notional)).await?;
+    return render(output, &order, &orders_table(std::slice::from_ref(&order)));
   }
 
   Ok(())
@@ -1,8 +1,45 @@
-use clap::{command, Arg, Command, value_parser};
+use clap::{command, Arg, ArgAction, Command, value_parser};
+use rust_decimal::Decimal;
+
+use crate::cli::utils::{parse_duration, parse_time};
 
 pub fn capture() -> clap::ArgMatches {
+  build().get_matches()
+}
+
+fn build() -> Command {
   command!()
     .about("This is a CLI tool for Alpaca-related actions")
+    .arg(
+      Arg::new("paper")
+        .long("paper")
+        .global(true)
+        .action(ArgAction::SetTrue)
+        .conflicts_with("live")
+        .help("Use the paper trading environment")
+    )
+    .arg(
+      Arg::new("live")
+        .long("live")
+        .global(true)
+        .action(ArgAction::SetTrue)
+        .help("Use the live trading environment, overriding the configured default")
+    )
+    .arg(
+      Arg::new("profile")
+        .long("profile")
+        .global(true)
+        .help("Credentials profile to use (defaults to $STOCK_TRADER_PROFILE, then \"default\")")
+    )
+    .arg(
+      Arg::new("output")
+        .short('o')
+        .long("output")
+        .global(true)
+        .value_parser(["table", "json", "csv"])
+        .default_value("table")
+        .help("Output format")
+    )
     .subcommand(
       Command::new("prices")
         .arg(
@@ -20,6 +57,61 @@ pub fn capture() -> clap::ArgMatches {
             .help("Multiple stock symbols (comma-separated) — fetched concurrently")
         )
     )
+    .subcommand(
+      Command::new("bars")
+        .about("Download historical OHLCV bars")
+        .arg(
+          Arg::new("symbol")
+            .short('s')
+            .long("symbol")
+            .aliases(["ticker", "tcker"])
+            .help("Single stock ticker or crypto pair")
+        )
+        .arg(
+          Arg::new("symbols")
+            .long("symbols")
+            .value_delimiter(',')
+            .num_args(1..)
+            .help("Multiple symbols (comma-separated) — fetched concurrently")
+        )
+        .arg(
+          Arg::new("timeframe")
+            .long("timeframe")
+            .value_parser(["1Min", "5Min", "1Hour", "1Day"])
+            .default_value("1Day")
+            .help("Bar size")
+        )
+        .arg(
+          Arg::new("start")
+            .long("start")
+            .required(true)
+            .value_parser(parse_time)
+            .help("Start date (2024-01-31) or RFC 3339 timestamp")
+        )
+        .arg(
+          Arg::new("end")
+            .long("end")
+            .value_parser(parse_time)
+            .help("End date or RFC 3339 timestamp (defaults to now)")
+        )
+        .arg(
+          Arg::new("adjustment")
+            .long("adjustment")
+            .value_parser(["raw", "split", "all"])
+            .default_value("raw")
+            .help("Corporate action adjustment for stock bars")
+        )
+        .arg(feed_arg())
+        .arg(
+          Arg::new("out")
+            .long("out")
+            .help("Write to this file instead of stdout")
+        )
+    )
+    .subcommand(
+      Command::new("account")
+        .about("Show buying power, equity, cash and day-trade status")
+    )
     .subcommand(
       Command::new("positions")
         .arg(
@@ -36,6 +128,61 @@ pub fn capture() -> clap::ArgMatches {
             .num_args(1..)         // One or more values
             .help("Multiple stock symbols (comma-separated) — fetched concurrently")
         )
+        .arg(
+          Arg::new("sort")
+            .long("sort")
+            .value_parser(["pl", "weight", "symbol"])
+            .default_value("symbol")
+            .help("Sort positions by unrealized P&L, portfolio weight or symbol")
+        )
+        .arg(
+          Arg::new("summary")
+            .long("summary")
+            .action(ArgAction::SetTrue)
+            .help("Append portfolio totals")
+        )
+        .subcommand(
+          Command::new("close")
+            .about("Liquidate one or all positions")
+            .arg(
+              Arg::new("symbol")
+                .short('s')
+                .long("symbol")
+                .aliases(["ticker", "tcker"])
+                .required_unless_present("all")
+                .help("Stock ticker symbol of the position to close")
+            )
+            .arg(
+              Arg::new("percentage")
+                .long("percentage")
+                .aliases(["percent", "pct"])
+                .value_parser(value_parser!(Decimal))
+                .requires("symbol")
+                .conflicts_with("all")
+                .help("Percentage of the position to close (0-100)")
+            )
+            .arg(
+              Arg::new("all")
+                .long("all")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("symbol")
+                .help("Close every open position")
+            )
+            .arg(
+              Arg::new("cancel-orders")
+                .long("cancel-orders")
+                .action(ArgAction::SetTrue)
+                .requires("all")
+                .help("Also cancel all open orders before closing")
+            )
+            .arg(
+              Arg::new("yes")
+                .short('y')
+                .long("yes")
+                .action(ArgAction::SetTrue)
+                .help("Skip the confirmation prompt")
+            )
+        )
     )
     .subcommand(
       Command::new("auth")
@@ -56,9 +203,43 @@ pub fn capture() -> clap::ArgMatches {
                 .help("Your APCA Secret Key ID from Alpaca")
             )
         )
-        .subcommand(Command::new("reset"))
+        .subcommand(
+          Command::new("reset")
+            .about("Delete the stored credentials for the profile")
+        )
+        .subcommand(
+          Command::new("status")
+            .about("Show the profile, masked key, environment and credentials file")
+        )
+        .subcommand(
+          Command::new("verify")
+            .about("Check the keys against Alpaca and report which environment they belong to")
+        )
+        .subcommand(
+          Command::new("encrypt")
+            .about("Encrypt the credentials file with a passphrase")
+        )
+        .subcommand(
+          Command::new("decrypt")
+            .about("Store the credentials file as plain JSON again")
+        )
         // TODO: rm
     )
+    .subcommand(
+      Command::new("config")
+        .about("Manage persisted defaults")
+        .subcommand(
+          Command::new("set")
+            .arg(
+              Arg::new("paper")
+                .long("paper")
+                .value_parser(value_parser!(bool))
+                .help("Default to the paper trading environment (true/false)")
+            )
+            .arg(feed_arg().help("Default market data feed"))
+        )
+        .subcommand(Command::new("show"))
+    )
     .subcommand(
       Command::new("orders")
         .subcommand(
@@ -91,10 +272,92 @@ pub fn capture() -> clap::ArgMatches {
               Arg::new("notional")
                 .short('n')
                 .long("notional")
-                .value_parser(value_parser!(f64))
+                .value_parser(value_parser!(Decimal))
                 .aliases(["value", "dollars"])
                 .help("Dollar amount of the stock order")
             )
+            .arg(
+              Arg::new("qty")
+                .short('q')
+                .long("qty")
+                .value_parser(value_parser!(Decimal))
+                .aliases(["quantity", "shares"])
+                .conflicts_with("notional")
+                .help("Number of shares to trade (fractional allowed)")
+            )
+            .arg(
+              Arg::new("type")
+                .long("type")
+                .value_parser(["market", "limit", "stop", "stop_limit", "trailing_stop"])
+                .default_value("market")
+                .help("Order type")
+            )
+            .arg(
+              Arg::new("limit-price")
+                .long("limit-price")
+                .value_parser(value_parser!(Decimal))
+                .help("Limit price for limit and stop_limit orders")
+            )
+            .arg(
+              Arg::new("stop-price")
+                .long("stop-price")
+                .value_parser(value_parser!(Decimal))
+                .help("Stop price for stop and stop_limit orders")
+            )
+            .arg(
+              Arg::new("trail-price")
+                .long("trail-price")
+                .value_parser(value_parser!(Decimal))
+                .conflicts_with("trail-percent")
+                .help("Dollar trail for trailing_stop orders")
+            )
+            .arg(
+              Arg::new("trail-percent")
+                .long("trail-percent")
+                .value_parser(value_parser!(Decimal))
+                .help("Percent trail for trailing_stop orders")
+            )
+            .arg(
+              Arg::new("tif")
+                .long("tif")
+                .aliases(["time-in-force"])
+                .value_parser(["day", "gtc", "opg", "cls", "ioc", "fok"])
+                .default_value("day")
+                .help("Time in force")
+            )
+            .arg(
+              Arg::new("class")
+                .long("class")
+                .aliases(["order-class"])
+                .value_parser(["simple", "bracket", "oco", "oto"])
+                .default_value("simple")
+                .help("Order class: simple, bracket, oco (one-cancels-other) or oto (one-triggers-other)")
+            )
+            .arg(
+              Arg::new("take-profit")
+                .long("take-profit")
+                .value_parser(value_parser!(Decimal))
+                .help("Limit price of the take-profit leg")
+            )
+            .arg(
+              Arg::new("stop-loss")
+                .long("stop-loss")
+                .value_parser(value_parser!(Decimal))
+                .help("Stop price of the stop-loss leg")
+            )
+            .arg(
+              Arg::new("stop-loss-limit")
+                .long("stop-loss-limit")
+                .value_parser(value_parser!(Decimal))
+                .requires("stop-loss")
+                .help("Optional limit price of the stop-loss leg")
+            )
+            .arg(
+              Arg::new("wait")
+                .long("wait")
+                .action(ArgAction::SetTrue)
+                .help("Block until the order is filled, cancelled or rejected and print the fill price")
+            )
         )
         .subcommand(
           Command::new("cancel")
@@ -102,9 +365,71 @@ pub fn capture() -> clap::ArgMatches {
               Arg::new("order_id")
                 .long("order_id")
                 .aliases(["orderid", "orderId", "order-id"])
-                .required(true)
+                .required_unless_present("all")
                 .help("Order ID to be cancelled (uuid v4 format)")
             )
+            .arg(
+              Arg::new("all")
+                .long("all")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("order_id")
+                .help("Cancel every open order")
+            )
+            .arg(
+              Arg::new("yes")
+                .short('y')
+                .long("yes")
+                .action(ArgAction::SetTrue)
+                .help("Skip the confirmation prompt")
+            )
+        )
+        .subcommand(
+          Command::new("replace")
+            .about("Modify an open order in place")
+            .arg(
+              Arg::new("order_id")
+                .long("order_id")
+                .aliases(["orderid", "orderId", "order-id"])
+                .required(true)
+                .help("Order ID to be replaced (uuid v4 format)")
+            )
+            .arg(
+              Arg::new("qty")
+                .short('q')
+                .long("qty")
+                .value_parser(value_parser!(Decimal))
+                .aliases(["quantity", "shares"])
+                .help("New share quantity")
+            )
+            .arg(
+              Arg::new("limit-price")
+                .long("limit-price")
+                .value_parser(value_parser!(Decimal))
+                .help("New limit price")
+            )
+            .arg(
+              Arg::new("stop-price")
+                .long("stop-price")
+                .value_parser(value_parser!(Decimal))
+                .help("New stop price")
+            )
+            .arg(
+              Arg::new("trail")
+                .long("trail")
+                .value_parser(value_parser!(Decimal))
+                .help("New trail price or percent for trailing_stop orders")
+            )
+            .arg(
+              Arg::new("tif")
+                .long("tif")
+                .aliases(["time-in-force"])
+                .value_parser(["day", "gtc", "opg", "cls", "ioc", "fok"])
+                .help("New time in force")
+            )
+        )
+        .subcommand(
+          Command::new("watch")
+            .about("Print fills, partial fills, cancels and rejections as they happen")
         )
         .subcommand(
           Command::new("randombuy")
@@ -113,7 +438,7 @@ pub fn capture() -> clap::ArgMatches {
                 .short('n')
                 .long("notional")
                 .required(true)
-                .value_parser(value_parser!(f64))
+                .value_parser(value_parser!(Decimal))
                 .aliases(["value", "dollars"])
                 .help("Dollar amount of the stock order")
             )
@@ -130,6 +455,16 @@ pub fn capture() -> clap::ArgMatches {
             .num_args(1..)
             .help("Symbols to stream (comma-separated)")
         )
+        .arg(
+          Arg::new("channels")
+            .long("channels")
+            .value_delimiter(',')
+            .num_args(1..)
+            .value_parser(["trades", "quotes", "bars", "daily-bars"])
+            .default_value("trades,quotes")
+            .help("Market data channels to show (comma-separated)")
+        )
+        .arg(feed_arg())
     )
     .subcommand(
       Command::new("chart")
@@ -142,6 +477,67 @@ pub fn capture() -> clap::ArgMatches {
             .num_args(1..)
             .help("Symbols to chart (comma-separated)")
         )
+        .arg(
+          Arg::new("source")
+            .long("source")
+            .value_parser(["quotes", "trades", "bars"])
+            .default_value("quotes")
+            .help("Plot the quote midpoint, trade prices or one-minute bars")
+        )
+        .arg(
+          Arg::new("lookback")
+            .long("lookback")
+            .value_parser(["1h", "1d", "5d"])
+            .default_value("1h")
+            .help("History to load from REST bars before live updates arrive")
+        )
+        .arg(
+          Arg::new("window")
+            .long("window")
+            .value_parser(parse_duration)
+            .help("Time span shown on the x axis, e.g. 15m or 4h (defaults to the lookback)")
+        )
+        .arg(
+          Arg::new("interval")
+            .long("interval")
+            .value_parser(parse_duration)
+            .default_value("1m")
+            .help("Candle size in candlestick mode (press c in the chart); at least the lookback's bar size")
+        )
+        .arg(feed_arg())
     )
-    .get_matches()
-}
\ No newline at end of file
+}
+
+fn feed_arg() -> Arg {
+  Arg::new("feed")
+    .long("feed")
+    .value_parser(["iex", "sip", "delayed_sip", "test"])
+    .help("Market data feed (defaults to the configured feed, then iex)")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use clap::parser::ValueSource;
+
+  #[test]
+  fn test_close_percentage_requires_a_single_symbol() {
+    let partial = build().try_get_matches_from(["stock-trader", "positions", "close", "-s", "AAPL", "--percentage", "5"]);
+    assert!(partial.is_ok());
+
+    let all = build().try_get_matches_from(["stock-trader", "positions", "close", "--all", "--percentage", "5", "-y"]);
+    assert_eq!(all.unwrap_err().kind(), clap::error::ErrorKind::ArgumentConflict);
+  }
+
+  #[test]
+  fn test_bars_sees_whether_output_was_given() {
+    let source = |args: &[&str]| {
+      let matches = build().try_get_matches_from(args).unwrap();
+      matches.subcommand_matches("bars").unwrap().value_source("output")
+    };
+
+    let base = ["stock-trader", "bars", "--symbol", "AAPL", "--start", "2024-01-01"];
+    assert_eq!(source(&base), Some(ValueSource::DefaultValue));
+    assert_eq!(source(&[&base[..], &["-o", "table"]].concat()), Some(ValueSource::CommandLine));
+  }
+}
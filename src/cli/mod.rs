@@ -1,3 +1,4 @@
 pub mod matches;
 pub mod cmd;
+pub mod output;
 pub mod utils;
\ No newline at end of file
@@ -0,0 +1,552 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Serialize;
+
+use crate::alpaca_api::{Account, Asset, Bar, Order, Snapshot};
+use crate::error::AppResult;
+use crate::portfolio::{PortfolioSummary, WeightedPosition};
+use crate::trade_updates::TradeUpdate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+  Table,
+  Json,
+  Csv,
+}
+
+impl OutputFormat {
+  pub fn from_arg(value: &str) -> Self {
+    match value {
+      "json" => OutputFormat::Json,
+      "csv" => OutputFormat::Csv,
+      _ => OutputFormat::Table,
+    }
+  }
+}
+
+pub struct Table {
+  headers: Vec<String>,
+  rows: Vec<Vec<String>>,
+  /// Nesting level of each row, drawn as a tree in the first column of the aligned table only.
+  depths: Vec<usize>,
+}
+
+impl Table {
+  pub fn new(headers: &[&str]) -> Self {
+    Self {
+      headers: headers.iter().map(|h| h.to_string()).collect(),
+      rows: Vec::new(),
+      depths: Vec::new(),
+    }
+  }
+
+  pub fn add_row(&mut self, row: Vec<String>) {
+    self.add_nested_row(row, 0);
+  }
+
+  pub fn add_nested_row(&mut self, row: Vec<String>, depth: usize) {
+    self.rows.push(row);
+    self.depths.push(depth);
+  }
+
+  pub fn render_table(&self) -> String {
+    let rows: Vec<Vec<String>> = self
+      .rows
+      .iter()
+      .zip(&self.depths)
+      .map(|(row, &depth)| indent_first_cell(row, depth))
+      .collect();
+
+    let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+      for (i, cell) in row.iter().enumerate() {
+        if let Some(width) = widths.get_mut(i) {
+          *width = (*width).max(cell.chars().count());
+        }
+      }
+    }
+
+    let format_row = |cells: &[String]| -> String {
+      let line: Vec<String> = cells
+        .iter()
+        .zip(&widths)
+        .map(|(cell, &width)| format!("{:<width$}", cell))
+        .collect();
+      line.join("  ").trim_end().to_string()
+    };
+
+    let mut out = format_row(&self.headers);
+    out.push('\n');
+    let separator: Vec<String> = widths.iter().map(|&w| "-".repeat(w)).collect();
+    out.push_str(&separator.join("  "));
+    out.push('\n');
+    for row in &rows {
+      out.push_str(&format_row(row));
+      out.push('\n');
+    }
+
+    out
+  }
+
+  pub fn render_csv(&self) -> String {
+    let mut out = String::new();
+    for row in std::iter::once(&self.headers).chain(&self.rows) {
+      let cells: Vec<String> = row.iter().map(|cell| csv_escape(cell)).collect();
+      out.push_str(&cells.join(","));
+      out.push('\n');
+    }
+
+    out
+  }
+}
+
+fn indent_first_cell(row: &[String], depth: usize) -> Vec<String> {
+  let mut row = row.to_vec();
+  if let (Some(first), true) = (row.first_mut(), depth > 0) {
+    *first = format!("{}└─ {}", "   ".repeat(depth - 1), first);
+  }
+  row
+}
+
+fn csv_escape(cell: &str) -> String {
+  if cell.contains([',', '"', '\n']) {
+    format!("\"{}\"", cell.replace('"', "\"\""))
+  } else {
+    cell.to_string()
+  }
+}
+
+/// Prints `value` as pretty JSON, or `table` as an aligned table or CSV.
+pub fn render<T: Serialize>(format: OutputFormat, value: &T, table: &Table) -> AppResult<()> {
+  match format {
+    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+    OutputFormat::Csv => print!("{}", table.render_csv()),
+    OutputFormat::Table => print!("{}", table.render_table()),
+  }
+
+  Ok(())
+}
+
+fn or_dash<T: std::fmt::Display>(value: Option<T>) -> String {
+  value.map_or_else(|| "-".to_string(), |v| v.to_string())
+}
+
+pub fn orders_table(orders: &[Order]) -> Table {
+  let mut table = Table::new(&[
+    "ID", "Symbol", "Side", "Type", "Class", "Qty", "Notional", "Limit", "Stop", "TIF", "Status",
+    "Created",
+  ]);
+  for order in orders {
+    add_order_rows(&mut table, order, 0);
+  }
+
+  table
+}
+
+fn add_order_rows(table: &mut Table, order: &Order, depth: usize) {
+  let row = vec![
+    order.id.clone(),
+    order.symbol.clone(),
+    order.side.clone(),
+    order.order_type.clone(),
+    order.order_class.clone().filter(|c| !c.is_empty()).unwrap_or_else(|| "simple".to_string()),
+    or_dash(order.qty.map(|d| d.normalize())),
+    or_dash(order.notional.map(money)),
+    or_dash(order.limit_price.map(money)),
+    or_dash(order.stop_price.map(money)),
+    or_dash(order.time_in_force.as_ref()),
+    order.status.clone(),
+    order.created_at.clone(),
+  ];
+  table.add_nested_row(row, depth);
+
+  for leg in order.legs.iter().flatten() {
+    add_order_rows(table, leg, depth + 1);
+  }
+}
+
+/// Fill quantity and average price of an order once it has finished.
+pub fn fill_table(order: &Order) -> Table {
+  let mut table = Table::new(&[
+    "ID", "Symbol", "Side", "Status", "Filled Qty", "Fill Price", "Filled At",
+  ]);
+  table.add_row(vec![
+    order.id.clone(),
+    order.symbol.clone(),
+    order.side.clone(),
+    order.status.clone(),
+    or_dash(order.filled_qty.map(|d| d.normalize())),
+    or_dash(order.filled_avg_price.map(money)),
+    or_dash(order.filled_at.as_ref()),
+  ]);
+
+  table
+}
+
+/// One line per `trade_updates` event, e.g. `FILL buy 10 AAPL @ 187.12`.
+pub fn trade_update_line(update: &TradeUpdate) -> String {
+  let order = &update.order;
+  let qty = update
+    .qty
+    .or(order.qty)
+    .map(|q| q.normalize().to_string())
+    .or_else(|| order.notional.map(|n| format!("${}", money(n))))
+    .unwrap_or_else(|| "-".to_string());
+  let price = update.price.map(|p| format!(" @ {}", unit_price(p))).unwrap_or_default();
+
+  format!(
+    "{} {:<16} {} {} {}{} [{}] {}",
+    update.timestamp.as_deref().unwrap_or("-"),
+    update.event.to_uppercase(),
+    order.side,
+    qty,
+    order.symbol,
+    price,
+    order.status,
+    order.id
+  )
+}
+
+// Decimal's `{:.2}` truncates, so round half away from zero before formatting
+fn cents(value: Decimal) -> Decimal {
+  value.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+}
+
+pub fn money(value: Decimal) -> String {
+  format!("{:.2}", cents(value))
+}
+
+fn signed_money(value: Decimal) -> String {
+  format!("{:+.2}", cents(value))
+}
+
+/// Per-unit prices under a dollar keep their own precision, since crypto pairs like SHIB/USD and
+/// sub-penny stocks trade in fractions of a cent; everything else is shown in cents.
+pub fn unit_price(value: Decimal) -> String {
+  if value.abs() >= Decimal::ONE || cents(value) == value {
+    money(value)
+  } else {
+    value.normalize().to_string()
+  }
+}
+
+fn signed_unit_price(value: Decimal) -> String {
+  if value.abs() >= Decimal::ONE || cents(value) == value {
+    signed_money(value)
+  } else {
+    format!("{:+}", value.normalize())
+  }
+}
+
+fn percent(fraction: Decimal) -> String {
+  format!("{:+.2}%", cents(fraction * Decimal::ONE_HUNDRED))
+}
+
+pub fn positions_table(positions: &[WeightedPosition]) -> Table {
+  let mut table = Table::new(&[
+    "Symbol", "Qty", "Avg Entry", "Price", "Market Value", "Cost Basis", "P&L", "P&L %",
+    "Today P&L", "Weight",
+  ]);
+  for WeightedPosition { position, weight } in positions {
+    table.add_row(vec![
+      position.symbol.clone(),
+      position.qty.normalize().to_string(),
+      unit_price(position.avg_entry_price),
+      unit_price(position.current_price),
+      money(position.market_value),
+      money(position.cost_basis),
+      signed_money(position.unrealized_pl),
+      percent(position.unrealized_plpc),
+      signed_money(position.unrealized_intraday_pl),
+      format!("{}%", money(*weight)),
+    ]);
+  }
+
+  table
+}
+
+pub fn summary_table(summary: &PortfolioSummary) -> Table {
+  let mut table = positions_table(&summary.positions);
+  let totals = &summary.totals;
+  table.add_row(vec![
+    "TOTAL".to_string(),
+    String::new(),
+    String::new(),
+    String::new(),
+    money(totals.market_value),
+    money(totals.cost_basis),
+    signed_money(totals.unrealized_pl),
+    percent(totals.unrealized_plpc),
+    signed_money(totals.unrealized_intraday_pl),
+    if summary.positions.is_empty() { "-".to_string() } else { "100.00%".to_string() },
+  ]);
+
+  table
+}
+
+#[derive(Serialize)]
+pub struct PriceRow {
+  pub symbol: String,
+  pub name: Option<String>,
+  pub snapshot: Snapshot,
+}
+
+impl PriceRow {
+  pub fn new(symbol: String, asset: Option<Asset>, snapshot: Snapshot) -> Self {
+    Self {
+      symbol,
+      name: asset.map(|a| a.name),
+      snapshot,
+    }
+  }
+}
+
+pub fn prices_table(rows: &[PriceRow]) -> Table {
+  let mut table = Table::new(&[
+    "Symbol", "Name", "Last", "Size", "Bid", "Ask", "Change", "Change %", "Volume",
+  ]);
+  for row in rows {
+    let snapshot = &row.snapshot;
+    let trade = snapshot.latest_trade.as_ref();
+    let quote = snapshot.latest_quote.as_ref();
+    let change = snapshot.day_change();
+
+    table.add_row(vec![
+      row.symbol.clone(),
+      or_dash(row.name.as_ref()),
+      or_dash(trade.map(|t| unit_price(t.price))),
+      or_dash(trade.map(|t| t.size.normalize())),
+      or_dash(quote.map(|q| unit_price(q.bid_price))),
+      or_dash(quote.map(|q| unit_price(q.ask_price))),
+      or_dash(change.map(|(c, _)| signed_unit_price(c))),
+      or_dash(change.map(|(_, p)| format!("{}%", signed_money(p)))),
+      or_dash(snapshot.daily_bar.as_ref().map(|b| b.volume.normalize())),
+    ]);
+  }
+
+  table
+}
+
+/// A historical bar tagged with its symbol, so several symbols can share one CSV or JSON lines file.
+/// Values are JSON numbers rather than decimal strings so dataframe tools load them as numeric columns.
+#[derive(Serialize)]
+pub struct BarRow {
+  pub symbol: String,
+  pub timestamp: DateTime<Utc>,
+  #[serde(with = "rust_decimal::serde::float")]
+  pub open: Decimal,
+  #[serde(with = "rust_decimal::serde::float")]
+  pub high: Decimal,
+  #[serde(with = "rust_decimal::serde::float")]
+  pub low: Decimal,
+  #[serde(with = "rust_decimal::serde::float")]
+  pub close: Decimal,
+  #[serde(with = "rust_decimal::serde::float")]
+  pub volume: Decimal,
+}
+
+impl BarRow {
+  pub fn new(symbol: &str, bar: Bar) -> Self {
+    Self {
+      symbol: symbol.to_string(),
+      timestamp: bar.timestamp,
+      open: bar.open,
+      high: bar.high,
+      low: bar.low,
+      close: bar.close,
+      volume: bar.volume,
+    }
+  }
+}
+
+// Full precision rather than cents: this is research data, and crypto trades well below a cent
+pub fn bars_table(rows: &[BarRow]) -> Table {
+  let mut table = Table::new(&["Symbol", "Timestamp", "Open", "High", "Low", "Close", "Volume"]);
+  for row in rows {
+    table.add_row(vec![
+      row.symbol.clone(),
+      row.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+      row.open.normalize().to_string(),
+      row.high.normalize().to_string(),
+      row.low.normalize().to_string(),
+      row.close.normalize().to_string(),
+      row.volume.normalize().to_string(),
+    ]);
+  }
+
+  table
+}
+
+/// JSON lines (one object per row) rather than a single array, so large exports can be streamed.
+pub fn render_json_lines<T: Serialize>(rows: &[T]) -> AppResult<String> {
+  let mut out = String::new();
+  for row in rows {
+    out.push_str(&serde_json::to_string(row)?);
+    out.push('\n');
+  }
+
+  Ok(out)
+}
+
+pub fn account_table(account: &Account) -> Table {
+  let change = account
+    .equity_change()
+    .map(|(c, p)| format!("{} ({}%)", signed_money(c), signed_money(p)))
+    .unwrap_or_else(|| "-".into());
+
+  let mut table = Table::new(&["Field", "Value"]);
+  let fields = [
+    ("Account", account.account_number.clone()),
+    ("Status", account.status.clone()),
+    ("Equity", money(account.equity)),
+    ("Today's change", change),
+    ("Cash", money(account.cash)),
+    ("Buying power", money(account.buying_power)),
+    ("Day trades (5 days)", account.daytrade_count.to_string()),
+    ("Pattern day trader", account.pattern_day_trader.to_string()),
+    ("Trading blocked", (account.trading_blocked || account.account_blocked).to_string()),
+  ];
+  for (field, value) in fields {
+    table.add_row(vec![field.to_string(), value]);
+  }
+
+  table
+}
+
+#[derive(Serialize)]
+pub struct AuthStatus {
+  pub profile: String,
+  pub source: String,
+  pub api_key: String,
+  pub environment: String,
+  pub file: String,
+  pub encrypted: bool,
+}
+
+pub fn auth_status_table(status: &AuthStatus) -> Table {
+  let mut table = Table::new(&["Field", "Value"]);
+  let fields = [
+    ("Profile", status.profile.clone()),
+    ("Source", status.source.clone()),
+    ("API key", status.api_key.clone()),
+    ("Environment", status.environment.clone()),
+    ("File", status.file.clone()),
+    ("Encrypted", status.encrypted.to_string()),
+  ];
+  for (field, value) in fields {
+    table.add_row(vec![field.to_string(), value]);
+  }
+
+  table
+}
+
+#[derive(Serialize)]
+pub struct AuthVerification {
+  pub profile: String,
+  pub api_key: String,
+  pub environment: String,
+  pub account_number: String,
+  pub account_status: String,
+}
+
+pub fn auth_verification_table(verification: &AuthVerification) -> Table {
+  let mut table = Table::new(&["Field", "Value"]);
+  let fields = [
+    ("Profile", verification.profile.clone()),
+    ("API key", verification.api_key.clone()),
+    ("Keys valid for", verification.environment.clone()),
+    ("Account", verification.account_number.clone()),
+    ("Account status", verification.account_status.clone()),
+  ];
+  for (field, value) in fields {
+    table.add_row(vec![field.to_string(), value]);
+  }
+
+  table
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::portfolio::Totals;
+  use rust_decimal_macros::dec;
+
+  fn sample() -> Table {
+    let mut table = Table::new(&["Symbol", "Qty"]);
+    table.add_row(vec!["AAPL".to_string(), "10".to_string()]);
+    table.add_row(vec!["GOOGL".to_string(), "2.5".to_string()]);
+    table
+  }
+
+  #[test]
+  fn test_render_table_aligns_columns() {
+    let expected = "Symbol  Qty\n------  ---\nAAPL    10\nGOOGL   2.5\n";
+    assert_eq!(sample().render_table(), expected);
+  }
+
+  #[test]
+  fn test_render_csv() {
+    assert_eq!(sample().render_csv(), "Symbol,Qty\nAAPL,10\nGOOGL,2.5\n");
+  }
+
+  #[test]
+  fn test_summary_table_formats_decimals() {
+    let summary = PortfolioSummary {
+      positions: Vec::new(),
+      totals: Totals {
+        market_value: dec!(1234.5),
+        cost_basis: dec!(1000),
+        unrealized_pl: dec!(234.5),
+        unrealized_plpc: dec!(0.2345),
+        unrealized_intraday_pl: dec!(-12.346),
+      },
+    };
+
+    let csv = summary_table(&summary).render_csv();
+    assert_eq!(csv.lines().nth(1), Some("TOTAL,,,,1234.50,1000.00,+234.50,+23.45%,-12.35,-"));
+  }
+
+  #[test]
+  fn test_unit_price_keeps_sub_cent_precision() {
+    assert_eq!(unit_price(dec!(187.125)), "187.13");
+    assert_eq!(unit_price(dec!(0.5)), "0.50");
+    assert_eq!(unit_price(dec!(0.00001234)), "0.00001234");
+    assert_eq!(signed_unit_price(dec!(-0.0000002)), "-0.0000002");
+  }
+
+  #[test]
+  fn test_nested_rows_indent_only_in_table() {
+    let mut table = Table::new(&["ID"]);
+    table.add_row(vec!["parent".to_string()]);
+    table.add_nested_row(vec!["leg".to_string()], 1);
+
+    assert_eq!(table.render_table(), "ID\n------\nparent\n└─ leg\n");
+    assert_eq!(table.render_csv(), "ID\nparent\nleg\n");
+  }
+
+  #[test]
+  fn test_bar_rows_are_json_numbers() {
+    let bar = Bar {
+      timestamp: "2024-03-01T15:30:00Z".parse().unwrap(),
+      open: dec!(187.1),
+      high: dec!(187.3),
+      low: dec!(186.9),
+      close: dec!(187.12),
+      volume: dec!(12345),
+    };
+
+    let json = render_json_lines(&[BarRow::new("AAPL", bar)]).unwrap();
+    assert_eq!(
+      json,
+      "{\"symbol\":\"AAPL\",\"timestamp\":\"2024-03-01T15:30:00Z\",\"open\":187.1,\"high\":187.3,\
+       \"low\":186.9,\"close\":187.12,\"volume\":12345.0}\n"
+    );
+  }
+
+  #[test]
+  fn test_csv_escapes_delimiters_and_quotes() {
+    let mut table = Table::new(&["Name"]);
+    table.add_row(vec!["Apple, Inc. \"AAPL\"".to_string()]);
+    assert_eq!(table.render_csv(), "Name\n\"Apple, Inc. \"\"AAPL\"\"\"\n");
+  }
+}
@@ -1,10 +1,50 @@
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
 use rand::Rng;
+use std::io::{self, Write};
 
 pub fn generate_random_number() -> i32 {
   let mut rng = rand::thread_rng();
   rng.gen_range(1..=1000000)
 }
 
+pub fn confirm(prompt: &str) -> io::Result<bool> {
+  eprint!("{} [y/N] ", prompt);
+  io::stderr().flush()?;
+
+  let mut answer = String::new();
+  io::stdin().read_line(&mut answer)?;
+
+  Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Parses `--start`/`--end` as either a date (midnight UTC) or an RFC 3339 timestamp.
+pub fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+  if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+    return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+  }
+
+  DateTime::parse_from_rfc3339(value)
+    .map(|time| time.with_timezone(&Utc))
+    .map_err(|_| format!("'{value}' is not a date (2024-01-31) or RFC 3339 timestamp"))
+}
+
+/// Parses a duration such as `90s`, `15m`, `4h` or `2d`.
+pub fn parse_duration(value: &str) -> Result<TimeDelta, String> {
+  let invalid = || format!("'{value}' is not a duration like 15m, 4h or 2d");
+  let split = value.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+  let (amount, unit) = value.split_at(split);
+  let amount: i64 = amount.parse().map_err(|_| invalid())?;
+
+  let duration = match unit {
+    "s" => TimeDelta::try_seconds(amount),
+    "m" => TimeDelta::try_minutes(amount),
+    "h" => TimeDelta::try_hours(amount),
+    "d" => TimeDelta::try_days(amount),
+    _ => None,
+  };
+  duration.filter(|d| *d > TimeDelta::zero()).ok_or_else(invalid)
+}
+
 pub fn select_random_stock<F>(mut candidates: Vec<String>, mut rand_fn: F) -> Option<String>
 where
   F: FnMut() -> i32,
@@ -102,4 +142,23 @@ mod tests {
 
     assert_eq!(result, Some("C".to_string()));
   }
+
+  #[test]
+  fn test_parse_time_accepts_dates_and_timestamps() {
+    assert_eq!(parse_time("2024-01-31").unwrap().to_rfc3339(), "2024-01-31T00:00:00+00:00");
+    assert_eq!(
+      parse_time("2024-01-31T09:30:00-05:00").unwrap().to_rfc3339(),
+      "2024-01-31T14:30:00+00:00"
+    );
+    assert!(parse_time("yesterday").is_err());
+  }
+
+  #[test]
+  fn test_parse_duration() {
+    assert_eq!(parse_duration("15m"), Ok(TimeDelta::minutes(15)));
+    assert_eq!(parse_duration("2d"), Ok(TimeDelta::days(2)));
+    assert!(parse_duration("0m").is_err());
+    assert!(parse_duration("15").is_err());
+    assert!(parse_duration("m").is_err());
+  }
 }
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::alpaca_api::{Environment, Feed};
+use crate::error::{AppError, AppResult};
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Config {
+  #[serde(default)]
+  pub paper: bool,
+  #[serde(default)]
+  pub feed: Feed,
+}
+
+fn get_config_path() -> AppResult<std::path::PathBuf> {
+  let home = dirs::home_dir()
+    .ok_or_else(|| AppError::Config("Could not determine home directory".into()))?;
+
+  Ok(home.join(".config").join("stock-trader").join("config.json"))
+}
+
+pub fn read_config() -> AppResult<Config> {
+  let config_path = get_config_path()?;
+  if !config_path.exists() {
+    return Ok(Config::default());
+  }
+
+  let contents = fs::read_to_string(config_path)?;
+  let config: Config = serde_json::from_str(&contents)?;
+
+  Ok(config)
+}
+
+pub fn write_config(config: &Config) -> AppResult<()> {
+  let config_path = get_config_path()?;
+  if let Some(dir) = config_path.parent() {
+    fs::create_dir_all(dir)?;
+  }
+
+  let json = serde_json::to_string_pretty(config)?;
+  fs::write(config_path, json)?;
+
+  Ok(())
+}
+
+/// `--paper`/`--live` beat the profile's environment, which beats the persisted default.
+pub fn resolve_environment(flag: Option<bool>, profile_paper: Option<bool>) -> AppResult<Environment> {
+  let paper = match flag.or(profile_paper) {
+    Some(paper) => paper,
+    None => read_config()?.paper,
+  };
+
+  Ok(if paper { Environment::Paper } else { Environment::Live })
+}
+
+/// `--feed` beats the persisted default.
+pub fn resolve_feed(flag: Option<&String>) -> AppResult<Feed> {
+  match flag {
+    Some(feed) => Ok(Feed::from_arg(feed)),
+    None => Ok(read_config()?.feed),
+  }
+}
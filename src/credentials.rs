@@ -1,39 +1,328 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
 use std::fs;
+use std::io::Write;
+use std::path::Path;
 
+use crate::encryption::{self, EncryptedBlob};
 use crate::error::{AppError, AppResult};
 
-#[derive(Serialize, Deserialize, Debug)]
+pub const DEFAULT_PROFILE: &str = "default";
+const API_KEY_ENV: &str = "APCA_API_KEY_ID";
+const SECRET_KEY_ENV: &str = "APCA_API_SECRET_KEY";
+const PROFILE_ENV: &str = "STOCK_TRADER_PROFILE";
+const PASSPHRASE_ENV: &str = "STOCK_TRADER_PASSPHRASE";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Credentials {
   #[serde(rename = "apcaApiKey")]
   pub apca_api_key: String,
   #[serde(rename = "apcaSecretKey")]
   pub apca_secret_key: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub paper: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct CredentialsFile {
+  profiles: BTreeMap<String, Credentials>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct EncryptedFile {
+  encrypted: EncryptedBlob,
+}
+
+// Files written before profiles existed hold a single flat credentials object
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredCredentials {
+  Encrypted(EncryptedFile),
+  Profiles(CredentialsFile),
+  Legacy(Credentials),
 }
 
-fn get_credentials_path() -> AppResult<std::path::PathBuf> {
+/// Decrypted credentials plus the passphrase needed to write them back encrypted.
+struct LoadedCredentials {
+  file: CredentialsFile,
+  passphrase: Option<String>,
+}
+
+pub fn get_credentials_path() -> AppResult<std::path::PathBuf> {
   let home = dirs::home_dir()
     .ok_or_else(|| AppError::Config("Could not determine home directory".into()))?;
 
   Ok(home.join(".config").join("stock-trader").join("credentials.json"))
 }
 
-fn read_credentials() -> AppResult<Credentials> {
+fn read_credentials_file() -> AppResult<LoadedCredentials> {
   let cred_path = get_credentials_path()?;
+  if !cred_path.exists() {
+    return Ok(LoadedCredentials {
+      file: CredentialsFile::default(),
+      passphrase: None,
+    });
+  }
+
+  warn_if_too_open(&cred_path);
   let contents = fs::read_to_string(cred_path)?;
-  let credentials: Credentials = serde_json::from_str(&contents)?;
 
-  Ok(credentials)
+  if let StoredCredentials::Encrypted(encrypted) = serde_json::from_str(&contents)? {
+    let passphrase = unlock_passphrase()?;
+    let plaintext = encryption::decrypt(&encrypted.encrypted, &passphrase)?;
+    let file = parse_credentials_file(&String::from_utf8_lossy(&plaintext))?;
+    return Ok(LoadedCredentials {
+      file,
+      passphrase: Some(passphrase),
+    });
+  }
+
+  Ok(LoadedCredentials {
+    file: parse_credentials_file(&contents)?,
+    passphrase: None,
+  })
 }
 
-pub fn write_credentials(credentials: &Credentials) -> AppResult<()> {
+fn parse_credentials_file(contents: &str) -> AppResult<CredentialsFile> {
+  let file = match serde_json::from_str(contents)? {
+    StoredCredentials::Profiles(file) => file,
+    StoredCredentials::Legacy(credentials) => CredentialsFile {
+      profiles: BTreeMap::from([(DEFAULT_PROFILE.to_string(), credentials)]),
+    },
+    StoredCredentials::Encrypted(_) => {
+      return Err(AppError::Config("Encrypted credentials contain another encrypted file".into()));
+    }
+  };
+
+  Ok(file)
+}
+
+fn write_credentials_file(file: &CredentialsFile, passphrase: Option<&str>) -> AppResult<()> {
   let cred_path = get_credentials_path()?;
-  let json = serde_json::to_string_pretty(credentials)?;
-  fs::write(cred_path, json)?;
+  let mut json = serde_json::to_string_pretty(file)?;
+  if let Some(passphrase) = passphrase {
+    let encrypted = EncryptedFile {
+      encrypted: encryption::encrypt(json.as_bytes(), passphrase)?,
+    };
+    json = serde_json::to_string_pretty(&encrypted)?;
+  }
+
+  write_private_file(&cred_path, json.as_bytes())
+}
 
+/// Writes through a 0600 temp file and renames it over `path`, so a crash never leaves a partial file.
+fn write_private_file(path: &Path, contents: &[u8]) -> AppResult<()> {
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir)?;
+    // The directory may already exist with default permissions, e.g. from `config set`
+    restrict_permissions(dir, 0o700)?;
+  }
+
+  let tmp_path = path.with_extension("json.tmp");
+  let mut options = fs::OpenOptions::new();
+  options.write(true).create(true).truncate(true);
+  #[cfg(unix)]
+  std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+  let mut tmp = options.open(&tmp_path)?;
+  // The creation mode is ignored when a stale temp file is left over from an earlier crash
+  restrict_permissions(&tmp_path, 0o600)?;
+  tmp.write_all(contents)?;
+  tmp.sync_all()?;
+  fs::rename(&tmp_path, path)?;
+
+  Ok(())
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path, mode: u32) -> AppResult<()> {
+  use std::os::unix::fs::PermissionsExt;
+
+  fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+  Ok(())
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path, _mode: u32) -> AppResult<()> {
   Ok(())
 }
 
-pub fn get_credentials() -> AppResult<Credentials> {
-  read_credentials()
+#[cfg(unix)]
+fn warn_if_too_open(path: &Path) {
+  if let Some(dir) = path.parent() {
+    warn_if_accessible(dir, "700");
+  }
+  warn_if_accessible(path, "600");
+}
+
+#[cfg(unix)]
+fn warn_if_accessible(path: &Path, fix: &str) {
+  use std::os::unix::fs::PermissionsExt;
+
+  if let Ok(metadata) = fs::metadata(path) {
+    let mode = metadata.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+      eprintln!(
+        "Warning: {} is accessible by other users (mode {:o}). Run `chmod {} {}`",
+        path.display(),
+        mode,
+        fix,
+        path.display()
+      );
+    }
+  }
+}
+
+#[cfg(not(unix))]
+fn warn_if_too_open(_path: &Path) {}
+
+fn unlock_passphrase() -> AppResult<String> {
+  if let Ok(passphrase) = env::var(PASSPHRASE_ENV) {
+    return Ok(passphrase);
+  }
+
+  Ok(rpassword::prompt_password("Passphrase for credentials: ")?)
+}
+
+fn new_passphrase() -> AppResult<String> {
+  if let Ok(passphrase) = env::var(PASSPHRASE_ENV) {
+    if passphrase.is_empty() {
+      return Err(AppError::Config(format!("{PASSPHRASE_ENV} is set but empty")));
+    }
+    return Ok(passphrase);
+  }
+
+  let passphrase = rpassword::prompt_password("New passphrase: ")?;
+  if passphrase.is_empty() {
+    return Err(AppError::Config("Passphrase must not be empty".into()));
+  }
+  if rpassword::prompt_password("Confirm passphrase: ")? != passphrase {
+    return Err(AppError::Config("Passphrases do not match".into()));
+  }
+
+  Ok(passphrase)
+}
+
+/// Resolves the profile name from `--profile`, then `STOCK_TRADER_PROFILE`, then the default.
+pub fn resolve_profile(profile: Option<&String>) -> String {
+  profile
+    .cloned()
+    .or_else(|| env::var(PROFILE_ENV).ok().filter(|p| !p.is_empty()))
+    .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+pub fn credentials_from_env() -> Option<Credentials> {
+  let api_key = env::var(API_KEY_ENV).ok().filter(|k| !k.is_empty())?;
+  let secret_key = env::var(SECRET_KEY_ENV).ok().filter(|k| !k.is_empty())?;
+
+  Some(Credentials {
+    apca_api_key: api_key,
+    apca_secret_key: secret_key,
+    paper: None,
+  })
+}
+
+pub fn write_credentials(profile: &str, credentials: &Credentials) -> AppResult<()> {
+  let mut loaded = read_credentials_file()?;
+  loaded.file.profiles.insert(profile.to_string(), credentials.clone());
+  write_credentials_file(&loaded.file, loaded.passphrase.as_deref())
+}
+
+/// Re-encrypts the credentials file under a new passphrase.
+pub fn encrypt_credentials() -> AppResult<()> {
+  let loaded = read_credentials_file()?;
+  let passphrase = new_passphrase()?;
+  write_credentials_file(&loaded.file, Some(&passphrase))
+}
+
+pub fn decrypt_credentials() -> AppResult<()> {
+  let loaded = read_credentials_file()?;
+  write_credentials_file(&loaded.file, None)
+}
+
+/// Removes `profile`, deleting the file once no profiles are left. Returns false if it wasn't stored.
+pub fn remove_credentials(profile: &str) -> AppResult<bool> {
+  let mut loaded = read_credentials_file()?;
+  if loaded.file.profiles.remove(profile).is_none() {
+    return Ok(false);
+  }
+
+  if loaded.file.profiles.is_empty() {
+    fs::remove_file(get_credentials_path()?)?;
+  } else {
+    write_credentials_file(&loaded.file, loaded.passphrase.as_deref())?;
+  }
+
+  Ok(true)
+}
+
+pub fn is_encrypted() -> AppResult<bool> {
+  let cred_path = get_credentials_path()?;
+  if !cred_path.exists() {
+    return Ok(false);
+  }
+
+  let contents = fs::read_to_string(cred_path)?;
+  Ok(matches!(serde_json::from_str(&contents)?, StoredCredentials::Encrypted(_)))
+}
+
+/// Keeps the first and last four characters of a key ID so it can be recognised but not reused.
+pub fn mask_key(key: &str) -> String {
+  let chars: Vec<char> = key.chars().collect();
+  if chars.len() <= 8 {
+    return "*".repeat(chars.len());
+  }
+
+  let head: String = chars[..4].iter().collect();
+  let tail: String = chars[chars.len() - 4..].iter().collect();
+  format!("{head}{}{tail}", "*".repeat(chars.len() - 8))
+}
+
+/// Environment variables take precedence over the credentials file.
+pub fn get_credentials(profile: &str) -> AppResult<Credentials> {
+  if let Some(credentials) = credentials_from_env() {
+    return Ok(credentials);
+  }
+
+  let mut loaded = read_credentials_file()?;
+  loaded.file.profiles.remove(profile).ok_or_else(|| {
+    AppError::Config(format!(
+      "No credentials for profile '{profile}'. Run `stock-trader auth set --profile {profile}` \
+       or set {API_KEY_ENV} and {SECRET_KEY_ENV}"
+    ))
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_legacy_file_as_default_profile() {
+    let file = parse_credentials_file(r#"{"apcaApiKey": "key", "apcaSecretKey": "secret"}"#).unwrap();
+
+    assert_eq!(file.profiles.len(), 1);
+    assert_eq!(file.profiles[DEFAULT_PROFILE].apca_api_key, "key");
+  }
+
+  #[test]
+  fn test_parse_profiles_file() {
+    let file = parse_credentials_file(
+      r#"{"profiles": {
+        "personal": {"apcaApiKey": "pk", "apcaSecretKey": "ps"},
+        "paper": {"apcaApiKey": "tk", "apcaSecretKey": "ts", "paper": true}
+      }}"#,
+    )
+    .unwrap();
+
+    assert_eq!(file.profiles["personal"].paper, None);
+    assert_eq!(file.profiles["paper"].paper, Some(true));
+  }
+
+  #[test]
+  fn test_mask_key() {
+    assert_eq!(mask_key("PKABCDEFGHIJKLMNWXYZ"), "PKAB************WXYZ");
+    assert_eq!(mask_key("short"), "*****");
+  }
 }
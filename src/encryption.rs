@@ -0,0 +1,87 @@
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+
+const KDF: &str = "argon2id";
+const SALT_LEN: usize = 16;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncryptedBlob {
+  pub kdf: String,
+  pub salt: String,
+  pub nonce: String,
+  pub ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> AppResult<Key> {
+  let mut key = Key::default();
+  Argon2::default()
+    .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+    .map_err(|e| AppError::Config(format!("Key derivation failed: {e}")))?;
+
+  Ok(key)
+}
+
+fn decode(field: &str, value: &str) -> AppResult<Vec<u8>> {
+  STANDARD
+    .decode(value)
+    .map_err(|_| AppError::Config(format!("Encrypted credentials have an invalid {field}")))
+}
+
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> AppResult<EncryptedBlob> {
+  let mut salt = [0u8; SALT_LEN];
+  OsRng.fill_bytes(&mut salt);
+
+  let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+  let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+  let ciphertext = cipher
+    .encrypt(&nonce, plaintext)
+    .map_err(|_| AppError::Config("Encrypting credentials failed".into()))?;
+
+  Ok(EncryptedBlob {
+    kdf: KDF.to_string(),
+    salt: STANDARD.encode(salt),
+    nonce: STANDARD.encode(nonce),
+    ciphertext: STANDARD.encode(ciphertext),
+  })
+}
+
+pub fn decrypt(blob: &EncryptedBlob, passphrase: &str) -> AppResult<Vec<u8>> {
+  if blob.kdf != KDF {
+    return Err(AppError::Config(format!("Unsupported key derivation '{}'", blob.kdf)));
+  }
+
+  let salt = decode("salt", &blob.salt)?;
+  let nonce = decode("nonce", &blob.nonce)?;
+  let ciphertext = decode("ciphertext", &blob.ciphertext)?;
+  if nonce.len() != 12 {
+    return Err(AppError::Config("Encrypted credentials have an invalid nonce".into()));
+  }
+
+  let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+  cipher
+    .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+    .map_err(|_| AppError::Config("Could not unlock credentials: wrong passphrase?".into()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_round_trip() {
+    let blob = encrypt(b"secret keys", "correct horse").unwrap();
+    assert_eq!(decrypt(&blob, "correct horse").unwrap(), b"secret keys");
+  }
+
+  #[test]
+  fn test_wrong_passphrase_fails() {
+    let blob = encrypt(b"secret keys", "correct horse").unwrap();
+    assert!(decrypt(&blob, "battery staple").is_err());
+  }
+}
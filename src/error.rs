@@ -17,6 +17,16 @@ pub enum AppError {
   #[error("API error: {0}")]
   Api(String),
 
+  #[error("Alpaca rejected the request (HTTP {status}{}): {message}", code.map(|c| format!(", code {c}")).unwrap_or_default())]
+  AlpacaApi {
+    status: u16,
+    code: Option<u64>,
+    message: String,
+  },
+
+  #[error("Invalid order: {0}")]
+  InvalidOrder(String),
+
   #[error("Missing required argument: {0}")]
   MissingArgument(String),
 }
@@ -1,52 +1,137 @@
 mod alpaca_api;
 mod cli;
+mod config;
 mod credentials;
+mod encryption;
 mod error;
+mod portfolio;
+mod trade_updates;
 mod tui;
 mod websocket;
 
+use cli::output::OutputFormat;
 use error::AppResult;
 use tokio::sync::mpsc;
 
 async fn run() -> AppResult<()> {
   let matches = cli::matches::capture();
 
+  let profile = credentials::resolve_profile(matches.get_one::<String>("profile"));
+  let environment_flag = if matches.get_flag("paper") {
+    Some(true)
+  } else if matches.get_flag("live") {
+    Some(false)
+  } else {
+    None
+  };
+
+  let output = OutputFormat::from_arg(
+    matches
+      .get_one::<String>("output")
+      .map_or("table", |s| s.as_str()),
+  );
+
   if let Some(auth_args) = matches.subcommand_matches("auth") {
-    return cli::cmd::handle_auth(auth_args);
+    return cli::cmd::handle_auth(auth_args, &profile, environment_flag, output).await;
+  }
+
+  if let Some(config_args) = matches.subcommand_matches("config") {
+    return cli::cmd::handle_config(config_args);
   }
 
-  let credentials = credentials::get_credentials()?;
+  let credentials = credentials::get_credentials(&profile)?;
   let api_key = credentials.apca_api_key;
   let api_secret = credentials.apca_secret_key;
+  let environment = config::resolve_environment(environment_flag, credentials.paper)?;
+
+  if matches.subcommand_matches("account").is_some() {
+    return cli::cmd::handle_account(&api_key, &api_secret, environment, output).await;
+  }
 
   if let Some(prices_args) = matches.subcommand_matches("prices") {
-    return cli::cmd::handle_prices(prices_args, &api_key, &api_secret).await;
+    return cli::cmd::handle_prices(prices_args, &api_key, &api_secret, environment, output).await;
+  }
+
+  if let Some(bars_args) = matches.subcommand_matches("bars") {
+    return cli::cmd::handle_bars(bars_args, &api_key, &api_secret, environment, output).await;
   }
 
   if let Some(positions_args) = matches.subcommand_matches("positions") {
-    return cli::cmd::handle_positions(positions_args, &api_key, &api_secret).await;
+    return cli::cmd::handle_positions(positions_args, &api_key, &api_secret, environment, output).await;
   }
 
   if let Some(orders_args) = matches.subcommand_matches("orders") {
-    return cli::cmd::handle_orders(orders_args, &api_key, &api_secret).await;
+    return cli::cmd::handle_orders(orders_args, &api_key, &api_secret, environment, output).await;
   }
 
   if let Some(stream_args) = matches.subcommand_matches("stream") {
     let symbols: Vec<String> = stream_args
       .get_many::<String>("symbols")
       .unwrap()
+      .map(|s| alpaca_api::normalize_symbol(s))
+      .collect();
+    let channels: Vec<String> = stream_args
+      .get_many::<String>("channels")
+      .unwrap()
       .cloned()
       .collect();
-    return websocket::stream_trades(&api_key, &api_secret, symbols).await;
+    let client = alpaca_api::AlpacaClient::new(api_key.clone(), api_secret.clone(), environment);
+    return websocket::stream_trades(
+      &client,
+      &api_key,
+      &api_secret,
+      symbols,
+      websocket::Channels::from_args(&channels),
+      config::resolve_feed(stream_args.get_one::<String>("feed"))?,
+    )
+    .await;
   }
 
   if let Some(chart_args) = matches.subcommand_matches("chart") {
     let symbols: Vec<String> = chart_args
       .get_many::<String>("symbols")
       .unwrap()
-      .cloned()
+      .map(|s| alpaca_api::normalize_symbol(s))
       .collect();
 
+    // Plot one-minute bars, trade prints, or the quote midpoint
+    let channels = match chart_args.get_one::<String>("source").map(|s| s.as_str()) {
+      Some("bars") => websocket::Channels {
+        bars: true,
+        ..Default::default()
+      },
+      Some("trades") => websocket::Channels {
+        trades: true,
+        ..Default::default()
+      },
+      _ => websocket::Channels {
+        quotes: true,
+        ..Default::default()
+      },
+    };
+
+    let feed = config::resolve_feed(chart_args.get_one::<String>("feed"))?;
+    // Fail before the TUI takes over the terminal
+    websocket::stream_url(&symbols, feed)?;
+
+    let client = alpaca_api::AlpacaClient::new(api_key.clone(), api_secret.clone(), environment);
+    let lookback = tui::Lookback::from_arg(
+      chart_args
+        .get_one::<String>("lookback")
+        .map_or("1h", |s| s.as_str()),
+    );
+    let window = chart_args
+      .get_one::<chrono::TimeDelta>("window")
+      .copied()
+      .unwrap_or_else(|| lookback.duration());
+    // Candles can't be finer than the backfilled bars without leaving gaps in the history
+    let interval = chart_args
+      .get_one::<chrono::TimeDelta>("interval")
+      .copied()
+      .unwrap_or_else(|| chrono::TimeDelta::minutes(1))
+      .max(lookback.bar_size());
+    let history = tui::backfill(&client, &symbols, lookback, feed).await;
+
     // Create channel for websocket -> TUI communication
     let (tx, rx) = mpsc::channel(100);
 
@@ -55,11 +140,11 @@ async fn run() -> AppResult<()> {
     let ws_key = api_key.clone();
     let ws_secret = api_secret.clone();
     tokio::spawn(async move {
-      let _ = websocket::stream_to_channel(&ws_key, &ws_secret, ws_symbols, tx).await;
+      let _ = websocket::stream_to_channel(&ws_key, &ws_secret, ws_symbols, channels, feed, tx).await;
     });
 
     // Run TUI (blocks until user quits)
-    return tui::run(symbols, rx).await;
+    return tui::run(symbols, history, window, interval, rx).await;
   }
 
   Ok(())
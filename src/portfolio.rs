@@ -0,0 +1,149 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::alpaca_api::Position;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+  Pl,
+  Weight,
+  Symbol,
+}
+
+impl SortKey {
+  pub fn from_arg(value: &str) -> Self {
+    match value {
+      "pl" => SortKey::Pl,
+      "weight" => SortKey::Weight,
+      _ => SortKey::Symbol,
+    }
+  }
+}
+
+#[derive(Debug, Serialize)]
+pub struct WeightedPosition {
+  #[serde(flatten)]
+  pub position: Position,
+  /// Share of the portfolio's gross market value, in percent.
+  pub weight: Decimal,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct Totals {
+  pub market_value: Decimal,
+  pub cost_basis: Decimal,
+  pub unrealized_pl: Decimal,
+  pub unrealized_plpc: Decimal,
+  pub unrealized_intraday_pl: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PortfolioSummary {
+  pub positions: Vec<WeightedPosition>,
+  pub totals: Totals,
+}
+
+/// Weighs each position by absolute market value so short positions count toward exposure.
+pub fn summarize(positions: Vec<Position>, sort: SortKey) -> PortfolioSummary {
+  let gross: Decimal = positions.iter().map(|p| p.market_value.abs()).sum();
+
+  let mut totals = Totals::default();
+  for p in &positions {
+    totals.market_value += p.market_value;
+    totals.cost_basis += p.cost_basis;
+    totals.unrealized_pl += p.unrealized_pl;
+    totals.unrealized_intraday_pl += p.unrealized_intraday_pl;
+  }
+  if !totals.cost_basis.is_zero() {
+    totals.unrealized_plpc = totals.unrealized_pl / totals.cost_basis.abs();
+  }
+
+  let mut weighted: Vec<WeightedPosition> = positions
+    .into_iter()
+    .map(|position| {
+      let weight = if gross.is_zero() {
+        Decimal::ZERO
+      } else {
+        position.market_value.abs() / gross * Decimal::ONE_HUNDRED
+      };
+      WeightedPosition { position, weight }
+    })
+    .collect();
+
+  match sort {
+    SortKey::Pl => weighted.sort_by_key(|p| std::cmp::Reverse(p.position.unrealized_pl)),
+    SortKey::Weight => weighted.sort_by_key(|p| std::cmp::Reverse(p.weight)),
+    SortKey::Symbol => weighted.sort_by(|a, b| a.position.symbol.cmp(&b.position.symbol)),
+  }
+
+  PortfolioSummary {
+    positions: weighted,
+    totals,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rust_decimal_macros::dec;
+
+  fn position(symbol: &str, market_value: Decimal, cost_basis: Decimal) -> Position {
+    Position {
+      symbol: symbol.to_string(),
+      side: Some("long".to_string()),
+      qty: dec!(1),
+      avg_entry_price: cost_basis,
+      cost_basis,
+      market_value,
+      current_price: market_value,
+      unrealized_pl: market_value - cost_basis,
+      unrealized_plpc: (market_value - cost_basis) / cost_basis,
+      unrealized_intraday_pl: Decimal::ZERO,
+      unrealized_intraday_plpc: Decimal::ZERO,
+    }
+  }
+
+  fn sample() -> Vec<Position> {
+    vec![
+      position("MSFT", dec!(250), dec!(200)),
+      position("AAPL", dec!(750), dec!(800)),
+    ]
+  }
+
+  #[test]
+  fn test_summarize_totals() {
+    let summary = summarize(sample(), SortKey::Symbol);
+
+    assert_eq!(summary.totals.market_value, dec!(1000));
+    assert_eq!(summary.totals.cost_basis, dec!(1000));
+    assert_eq!(summary.totals.unrealized_pl, dec!(0));
+  }
+
+  #[test]
+  fn test_summarize_weights() {
+    let summary = summarize(sample(), SortKey::Symbol);
+    let weights: Vec<Decimal> = summary.positions.iter().map(|p| p.weight).collect();
+
+    assert_eq!(weights, vec![dec!(75), dec!(25)]);
+  }
+
+  #[test]
+  fn test_summarize_sorts_by_pl() {
+    let summary = summarize(sample(), SortKey::Pl);
+    let symbols: Vec<&str> = summary
+      .positions
+      .iter()
+      .map(|p| p.position.symbol.as_str())
+      .collect();
+
+    assert_eq!(symbols, vec!["MSFT", "AAPL"]);
+  }
+
+  #[test]
+  fn test_summarize_empty_portfolio() {
+    let summary = summarize(Vec::new(), SortKey::Weight);
+
+    assert!(summary.positions.is_empty());
+    assert_eq!(summary.totals.unrealized_plpc, Decimal::ZERO);
+  }
+}
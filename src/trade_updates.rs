@@ -0,0 +1,169 @@
+use futures::{SinkExt, StreamExt};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio::time::sleep;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use crate::alpaca_api::{Environment, Order};
+use crate::error::{AppError, AppResult};
+use crate::websocket::{reconnect_backoff, MAX_RECONNECT_ATTEMPTS};
+
+/// An order event from the trading `trade_updates` stream.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TradeUpdate {
+  pub event: String,
+  pub order: Order,
+  pub timestamp: Option<String>,
+  /// Fill price and size for `fill` and `partial_fill` events.
+  pub price: Option<Decimal>,
+  pub qty: Option<Decimal>,
+  pub position_qty: Option<Decimal>,
+}
+
+#[derive(Deserialize)]
+struct StreamEnvelope {
+  stream: String,
+  data: Value,
+}
+
+pub struct TradeUpdateStream {
+  ws: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl TradeUpdateStream {
+  pub async fn connect(api_key: &str, api_secret: &str, environment: Environment) -> AppResult<Self> {
+    let (mut ws, _) = connect_async(environment.stream_url())
+      .await
+      .map_err(|e| AppError::Api(format!("Could not connect to the trade_updates stream: {e}")))?;
+
+    let auth = json!({ "action": "auth", "key": api_key, "secret": api_secret });
+    send(&mut ws, &auth).await?;
+
+    let mut stream = Self { ws };
+    let reply = stream
+      .next_envelope()
+      .await?
+      .ok_or_else(|| AppError::Api("Connection closed during authentication".into()))?;
+    if reply.stream != "authorization" || reply.data["status"] != "authorized" {
+      return Err(AppError::Api(format!(
+        "trade_updates stream rejected the credentials for {}",
+        environment.label()
+      )));
+    }
+
+    let listen = json!({ "action": "listen", "data": { "streams": ["trade_updates"] } });
+    send(&mut stream.ws, &listen).await?;
+
+    Ok(stream)
+  }
+
+  /// Connects with exponential backoff, giving up after `MAX_RECONNECT_ATTEMPTS`.
+  pub async fn connect_with_retry(
+    api_key: &str,
+    api_secret: &str,
+    environment: Environment,
+  ) -> AppResult<Self> {
+    let mut attempt = 0;
+
+    loop {
+      attempt += 1;
+
+      match Self::connect(api_key, api_secret, environment).await {
+        Ok(stream) => return Ok(stream),
+        Err(e) if attempt >= MAX_RECONNECT_ATTEMPTS => return Err(e),
+        Err(e) => {
+          let backoff = reconnect_backoff(attempt);
+          eprintln!(
+            "{e}. Reconnecting in {}ms (attempt {}/{MAX_RECONNECT_ATTEMPTS})...",
+            backoff.as_millis(),
+            attempt + 1
+          );
+          sleep(backoff).await;
+        }
+      }
+    }
+  }
+
+  /// Waits for the next trade update, or `None` once the connection closes.
+  pub async fn next(&mut self) -> AppResult<Option<TradeUpdate>> {
+    while let Some(envelope) = self.next_envelope().await? {
+      if let Some(update) = parse_trade_update(envelope)? {
+        return Ok(Some(update));
+      }
+    }
+
+    Ok(None)
+  }
+
+  // The trading stream sends its JSON in binary frames
+  async fn next_envelope(&mut self) -> AppResult<Option<StreamEnvelope>> {
+    loop {
+      let text = match self.ws.next().await {
+        Some(Ok(Message::Text(text))) => text,
+        Some(Ok(Message::Binary(bytes))) => String::from_utf8_lossy(&bytes).into_owned(),
+        Some(Ok(Message::Close(_))) | None => return Ok(None),
+        Some(Ok(_)) => continue,
+        Some(Err(e)) => return Err(AppError::Api(format!("trade_updates stream error: {e}"))),
+      };
+
+      return Ok(Some(serde_json::from_str(&text)?));
+    }
+  }
+}
+
+async fn send(ws: &mut WebSocketStream<MaybeTlsStream<TcpStream>>, message: &Value) -> AppResult<()> {
+  ws.send(Message::Text(message.to_string()))
+    .await
+    .map_err(|e| AppError::Api(format!("trade_updates stream error: {e}")))
+}
+
+fn parse_trade_update(envelope: StreamEnvelope) -> AppResult<Option<TradeUpdate>> {
+  if envelope.stream != "trade_updates" {
+    return Ok(None);
+  }
+
+  Ok(Some(serde_json::from_value(envelope.data)?))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rust_decimal_macros::dec;
+
+  #[test]
+  fn test_parse_fill_event() {
+    let envelope: StreamEnvelope = serde_json::from_str(
+      r#"{"stream": "trade_updates", "data": {
+        "event": "fill",
+        "timestamp": "2024-03-01T15:30:00.123Z",
+        "price": "187.12",
+        "qty": "10",
+        "position_qty": "10",
+        "order": {
+          "id": "61e69015-8549-4bfd-b9c3-01e75843f47d",
+          "symbol": "AAPL", "side": "buy", "qty": "10", "notional": null,
+          "status": "filled", "type": "market", "time_in_force": "day",
+          "limit_price": null, "stop_price": null, "trail_price": null, "trail_percent": null,
+          "order_class": "", "legs": null, "filled_qty": "10", "filled_avg_price": "187.12",
+          "filled_at": "2024-03-01T15:30:00.123Z", "created_at": "2024-03-01T15:29:59.001Z"
+        }
+      }}"#,
+    )
+    .unwrap();
+
+    let update = parse_trade_update(envelope).unwrap().unwrap();
+    assert_eq!(update.event, "fill");
+    assert_eq!(update.price, Some(dec!(187.12)));
+    assert!(update.order.is_terminal());
+  }
+
+  #[test]
+  fn test_ignores_other_streams() {
+    let envelope: StreamEnvelope =
+      serde_json::from_str(r#"{"stream": "listening", "data": {"streams": ["trade_updates"]}}"#).unwrap();
+
+    assert!(parse_trade_update(envelope).unwrap().is_none());
+  }
+}
@@ -16,11 +16,15 @@ use ratatui::{
     widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph},
     Frame, Terminal,
 };
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 use tokio::sync::mpsc;
 
+use crate::alpaca_api::{AlpacaClient, Bar, BarsOptions, Feed};
+use crate::cli::output::unit_price;
 use crate::error::AppResult;
 
-const MAX_POINTS: usize = 100;
 const COLORS: [Color; 6] = [
     Color::Cyan,
     Color::Yellow,
@@ -30,101 +34,445 @@ const COLORS: [Color; 6] = [
     Color::Blue,
 ];
 
+/// Points kept per symbol across the window, about one per braille column on a wide terminal.
+const MAX_POINTS: i32 = 500;
+
 #[derive(Debug, Clone)]
 pub struct PriceUpdate {
     pub symbol: String,
-    pub price: f64,
+    pub price: Decimal,
+    /// Exchange timestamp, used as the x coordinate.
+    pub time: DateTime<Utc>,
+}
+
+/// How much history to backfill when the chart opens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookback {
+    Hour,
+    Day,
+    FiveDays,
+}
+
+impl Lookback {
+    pub fn from_arg(value: &str) -> Self {
+        match value {
+            "1d" => Lookback::Day,
+            "5d" => Lookback::FiveDays,
+            _ => Lookback::Hour,
+        }
+    }
+
+    pub fn duration(&self) -> TimeDelta {
+        match self {
+            Lookback::Hour => TimeDelta::hours(1),
+            Lookback::Day => TimeDelta::days(1),
+            Lookback::FiveDays => TimeDelta::days(5),
+        }
+    }
+
+    // Roughly a screenful of points for each lookback
+    fn timeframe(&self) -> &'static str {
+        match self {
+            Lookback::Hour => "1Min",
+            Lookback::Day => "15Min",
+            Lookback::FiveDays => "1Hour",
+        }
+    }
+
+    /// Length of one backfilled bar; candles finer than this would leave gaps in the history.
+    pub fn bar_size(&self) -> TimeDelta {
+        match self {
+            Lookback::Hour => TimeDelta::minutes(1),
+            Lookback::Day => TimeDelta::minutes(15),
+            Lookback::FiveDays => TimeDelta::hours(1),
+        }
+    }
+}
+
+// Far enough back to reach the last session over a long weekend
+const MARKET_CLOSED_SLACK_DAYS: i64 = 4;
+
+/// Fetches recent bars for each symbol. The lookback is measured from the latest bar rather than
+/// from now, so the chart shows the last session when the market is closed.
+pub async fn backfill(
+    client: &AlpacaClient,
+    symbols: &[String],
+    lookback: Lookback,
+    feed: Feed,
+) -> Vec<(String, Vec<Bar>)> {
+    let start = Utc::now() - lookback.duration() - TimeDelta::days(MARKET_CLOSED_SLACK_DAYS);
+    let options = BarsOptions {
+        feed,
+        ..Default::default()
+    };
+    let futures = symbols
+        .iter()
+        .map(|symbol| client.fetch_bars(symbol, lookback.timeframe(), start, None, options));
+    let results = futures::future::join_all(futures).await;
+
+    symbols
+        .iter()
+        .zip(results)
+        .filter_map(|(symbol, result)| match result {
+            Ok(bars) => Some((symbol.clone(), within_lookback(bars, lookback))),
+            Err(e) => {
+                eprintln!("Could not load history for {symbol}: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+fn within_lookback(bars: Vec<Bar>, lookback: Lookback) -> Vec<Bar> {
+    let Some(latest) = bars.last().map(|bar| bar.timestamp) else {
+        return bars;
+    };
+
+    let cutoff = latest - lookback.duration();
+    bars.into_iter().filter(|bar| bar.timestamp > cutoff).collect()
+}
+
+#[derive(Debug, Clone)]
+pub enum ChartUpdate {
+    Price(PriceUpdate),
+    /// A bar, plotted at its start time. A bar with the same timestamp as an earlier one revises it.
+    Bar { symbol: String, bar: Bar },
+}
+
+/// One OHLC interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candle {
+    start: DateTime<Utc>,
+    open: Decimal,
+    high: Decimal,
+    low: Decimal,
+    close: Decimal,
+    /// Times of the earliest and latest updates, so late revisions don't move the open or close.
+    opened: DateTime<Utc>,
+    updated: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChartMode {
+    Line,
+    Candles,
 }
 
 pub struct App {
-    prices: HashMap<String, VecDeque<f64>>,
+    prices: HashMap<String, VecDeque<(DateTime<Utc>, Decimal)>>,
+    candles: HashMap<String, VecDeque<Candle>>,
     symbols: Vec<String>,
-    y_min: f64,
-    y_max: f64,
+    window: TimeDelta,
+    interval: TimeDelta,
+    mode: ChartMode,
+    /// Symbol shown in candle mode, which draws one symbol at a time.
+    selected: usize,
+    /// Plot percent change from the first visible point instead of price.
+    percent: bool,
+    /// One small chart per symbol, each with its own y axis.
+    grid: bool,
     chart_data: Vec<Vec<(f64, f64)>>,
+    /// Set when prices change, so `chart_data` is rebuilt once per frame rather than per update.
+    dirty: bool,
 }
 
 impl App {
-    pub fn new(symbols: Vec<String>) -> Self {
+    pub fn new(symbols: Vec<String>, window: TimeDelta, interval: TimeDelta) -> Self {
         let mut prices = HashMap::new();
+        let mut candles = HashMap::new();
         let mut chart_data = Vec::new();
         for sym in &symbols {
-            prices.insert(sym.clone(), VecDeque::with_capacity(MAX_POINTS));
+            prices.insert(sym.clone(), VecDeque::new());
+            candles.insert(sym.clone(), VecDeque::new());
             chart_data.push(Vec::new());
         }
         Self {
             prices,
+            candles,
             symbols,
-            y_min: 0.0,
-            y_max: 100.0,
+            window,
+            interval,
+            mode: ChartMode::Line,
+            selected: 0,
+            percent: false,
+            grid: false,
             chart_data,
+            dirty: false,
         }
     }
 
-    pub fn update(&mut self, update: PriceUpdate) {
-        if let Some(history) = self.prices.get_mut(&update.symbol) {
-            history.push_back(update.price);
-            if history.len() > MAX_POINTS {
-                history.pop_front();
+    pub fn seed(&mut self, history: Vec<(String, Vec<Bar>)>) {
+        for (symbol, bars) in history {
+            for bar in bars {
+                self.apply(ChartUpdate::Bar {
+                    symbol: symbol.clone(),
+                    bar,
+                });
+            }
+        }
+    }
+
+    pub fn apply(&mut self, update: ChartUpdate) {
+        match update {
+            ChartUpdate::Price(update) => {
+                let p = update.price;
+                self.add_to_candle(&update.symbol, update.time, p, p, p, p);
+                self.update(update);
+            }
+            ChartUpdate::Bar { symbol, bar } => {
+                self.add_to_candle(&symbol, bar.timestamp, bar.open, bar.high, bar.low, bar.close);
+                self.update(PriceUpdate {
+                    symbol,
+                    price: bar.close,
+                    time: bar.timestamp,
+                });
             }
-            self.recalculate_bounds();
-            self.rebuild_chart_data();
         }
     }
 
-    fn recalculate_bounds(&mut self) {
-        self.y_min = f64::MAX;
-        self.y_max = f64::MIN;
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            ChartMode::Line => ChartMode::Candles,
+            ChartMode::Candles => ChartMode::Line,
+        };
+    }
+
+    pub fn toggle_percent(&mut self) {
+        self.percent = !self.percent;
+    }
+
+    pub fn toggle_grid(&mut self) {
+        self.grid = !self.grid;
+    }
+
+    pub fn select_next(&mut self, step: isize) {
+        let len = self.symbols.len() as isize;
+        self.selected = (self.selected as isize + step).rem_euclid(len) as usize;
+    }
+
+    // Merging is idempotent for highs and lows, so a revised bar can be folded in again; it only
+    // moves the open or close when it is the earliest or latest update the candle has seen
+    fn add_to_candle(
+        &mut self,
+        symbol: &str,
+        time: DateTime<Utc>,
+        open: Decimal,
+        high: Decimal,
+        low: Decimal,
+        close: Decimal,
+    ) {
+        let Some(candles) = self.candles.get_mut(symbol) else {
+            return;
+        };
 
-        for history in self.prices.values() {
-            for &price in history {
-                if price < self.y_min {
-                    self.y_min = price;
+        let start = interval_start(time, self.interval);
+        let pos = candles.partition_point(|c| c.start < start);
+        match candles.get_mut(pos) {
+            Some(candle) if candle.start == start => {
+                candle.high = candle.high.max(high);
+                candle.low = candle.low.min(low);
+                if time < candle.opened {
+                    candle.open = open;
+                    candle.opened = time;
                 }
-                if price > self.y_max {
-                    self.y_max = price;
+                if time >= candle.updated {
+                    candle.close = close;
+                    candle.updated = time;
                 }
             }
+            _ => candles.insert(
+                pos,
+                Candle {
+                    start,
+                    open,
+                    high,
+                    low,
+                    close,
+                    opened: time,
+                    updated: time,
+                },
+            ),
         }
 
-        if self.y_min == f64::MAX {
-            self.y_min = 0.0;
-            self.y_max = 100.0;
-        } else {
-            let padding = (self.y_max - self.y_min) * 0.05;
-            self.y_min -= padding;
-            self.y_max += padding;
+        if let Some(latest) = candles.back().map(|c| c.start) {
+            let cutoff = latest - self.window;
+            while candles.front().is_some_and(|c| c.start < cutoff) {
+                candles.pop_front();
+            }
+        }
+    }
+
+    pub fn update(&mut self, update: PriceUpdate) {
+        if let Some(history) = self.prices.get_mut(&update.symbol) {
+            insert_point(history, update.time, update.price, self.window / MAX_POINTS);
+
+            if let Some(&(latest, _)) = history.back() {
+                let cutoff = latest - self.window;
+                while history.front().is_some_and(|&(time, _)| time < cutoff) {
+                    history.pop_front();
+                }
+            }
+
+            self.dirty = true;
+        }
+    }
 
-            if (self.y_max - self.y_min).abs() < 0.01 {
-                self.y_min -= 1.0;
-                self.y_max += 1.0;
+    /// Follows the wall clock while data is flowing, and otherwise ends at the last point so a
+    /// closed market still shows its final stretch.
+    fn time_bounds(&self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        let latest = self
+            .prices
+            .values()
+            .filter_map(|history| history.back().map(|&(time, _)| time))
+            .max();
+
+        let end = match latest {
+            Some(latest) if now - latest > self.window => latest,
+            _ => now,
+        };
+
+        (end - self.window, end)
+    }
+
+    /// Points for symbol `i` from `x_min` on, rebased to percent change from the first of them
+    /// when normalized, so symbols at very different prices share one scale.
+    fn visible_series(&self, i: usize, x_min: f64) -> Vec<(f64, f64)> {
+        let data = &self.chart_data[i];
+        let data = &data[data.partition_point(|&(x, _)| x < x_min)..];
+        match data.first() {
+            Some(&(_, base)) if self.percent && base != 0.0 => {
+                data.iter().map(|&(x, y)| (x, (y / base - 1.0) * 100.0)).collect()
             }
+            _ => data.to_vec(),
         }
     }
 
-    fn rebuild_chart_data(&mut self) {
+    /// Converts changed prices to chart points; called before each frame is drawn.
+    pub fn refresh(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+
         for (i, sym) in self.symbols.iter().enumerate() {
             if let Some(history) = self.prices.get(sym) {
                 self.chart_data[i] = history
                     .iter()
-                    .enumerate()
-                    .map(|(x, &price)| (x as f64, price))
+                    .filter_map(|&(time, price)| Some((x_value(time), price.to_f64()?)))
                     .collect();
             }
         }
     }
 }
 
-pub async fn run(symbols: Vec<String>, mut rx: mpsc::Receiver<PriceUpdate>) -> AppResult<()> {
+// Keeps points in time order with only the latest point per `slot`, so busy symbols stay bounded;
+// a point at an existing timestamp (such as a revised bar) replaces it
+fn insert_point(
+    history: &mut VecDeque<(DateTime<Utc>, Decimal)>,
+    time: DateTime<Utc>,
+    price: Decimal,
+    slot: TimeDelta,
+) {
+    let bucket = interval_start(time, slot);
+    let pos = history.partition_point(|&(t, _)| t < time);
+
+    if let Some(point) = history.get_mut(pos).filter(|p| interval_start(p.0, slot) == bucket) {
+        // A later point already stands for this slot
+        if point.0 == time {
+            point.1 = price;
+        }
+        return;
+    }
+
+    match pos.checked_sub(1).and_then(|prev| history.get_mut(prev)) {
+        Some(point) if interval_start(point.0, slot) == bucket => *point = (time, price),
+        _ => history.insert(pos, (time, price)),
+    }
+}
+
+fn interval_start(time: DateTime<Utc>, interval: TimeDelta) -> DateTime<Utc> {
+    let secs = interval.num_seconds().max(1);
+    let ts = time.timestamp();
+    DateTime::from_timestamp(ts - ts.rem_euclid(secs), 0).unwrap_or(time)
+}
+
+fn interval_label(interval: TimeDelta) -> String {
+    let secs = interval.num_seconds();
+    match secs {
+        s if s % 86_400 == 0 => format!("{}d", s / 86_400),
+        s if s % 3_600 == 0 => format!("{}h", s / 3_600),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{s}s"),
+    }
+}
+
+// Pads the range by 5% so lines don't sit on the chart border. A flat series gets ±0.1% of its
+// level, so sub-cent crypto isn't stretched over a dollar of axis
+fn y_bounds(values: impl IntoIterator<Item = f64>) -> (f64, f64) {
+    let (mut y_min, mut y_max) = (f64::MAX, f64::MIN);
+    for value in values {
+        y_min = y_min.min(value);
+        y_max = y_max.max(value);
+    }
+
+    if y_min == f64::MAX {
+        return (0.0, 100.0);
+    }
+
+    let padding = (y_max - y_min) * 0.05;
+    let level = y_min.abs().max(y_max.abs());
+    if y_max - y_min + 2.0 * padding < level * 1e-4 || y_max == y_min {
+        let half = if level > 0.0 { level * 1e-3 } else { 1.0 };
+        let mid = (y_min + y_max) / 2.0;
+        return (mid - half, mid + half);
+    }
+    (y_min - padding, y_max + padding)
+}
+
+/// Splits `area` into a near-square grid with one cell per symbol.
+fn grid_cells(area: Rect, count: usize) -> Vec<Rect> {
+    let cols = (1..=count).find(|c| c * c >= count).unwrap_or(1);
+    let rows = count.div_ceil(cols);
+
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Ratio(1, rows as u32); rows])
+        .split(area)
+        .iter()
+        .flat_map(|row| {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Ratio(1, cols as u32); cols])
+                .split(*row)
+                .to_vec()
+        })
+        .take(count)
+        .collect()
+}
+
+// Seconds since the epoch, with millisecond precision
+fn x_value(time: DateTime<Utc>) -> f64 {
+    time.timestamp_millis() as f64 / 1000.0
+}
+
+pub async fn run(
+    symbols: Vec<String>,
+    history: Vec<(String, Vec<Bar>)>,
+    window: TimeDelta,
+    interval: TimeDelta,
+    mut rx: mpsc::Receiver<ChartUpdate>,
+) -> AppResult<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new(symbols);
+    let mut app = App::new(symbols, window, interval);
+    app.seed(history);
 
     loop {
+        app.refresh();
         terminal.draw(|f| draw_ui(f, &app))?;
 
         if event::poll(Duration::from_millis(50))? {
@@ -132,6 +480,11 @@ pub async fn run(symbols: Vec<String>, mut rx: mpsc::Receiver<PriceUpdate>) -> A
                 if key.kind == KeyEventKind::Press {
                     match key.code {
                         KeyCode::Char('q') | KeyCode::Esc => break,
+                        KeyCode::Char('c') => app.toggle_mode(),
+                        KeyCode::Char('p') => app.toggle_percent(),
+                        KeyCode::Char('g') => app.toggle_grid(),
+                        KeyCode::Tab | KeyCode::Right => app.select_next(1),
+                        KeyCode::BackTab | KeyCode::Left => app.select_next(-1),
                         _ => {}
                     }
                 }
@@ -139,7 +492,7 @@ pub async fn run(symbols: Vec<String>, mut rx: mpsc::Receiver<PriceUpdate>) -> A
         }
 
         while let Ok(update) = rx.try_recv() {
-            app.update(update);
+            app.apply(update);
         }
     }
 
@@ -157,72 +510,210 @@ fn draw_ui(f: &mut Frame, app: &App) {
         .constraints([Constraint::Min(0), Constraint::Length(3)])
         .split(f.area());
 
-    draw_chart(f, app, chunks[0]);
+    if app.grid {
+        for (i, cell) in grid_cells(chunks[0], app.symbols.len()).into_iter().enumerate() {
+            match app.mode {
+                ChartMode::Line => draw_chart(f, app, &[i], &format!(" {} ", app.symbols[i]), cell),
+                ChartMode::Candles => draw_candles(f, app, i, cell),
+            }
+        }
+    } else {
+        match app.mode {
+            ChartMode::Line => {
+                let all: Vec<usize> = (0..app.symbols.len()).collect();
+                draw_chart(f, app, &all, " Live Prices ", chunks[0]);
+            }
+            ChartMode::Candles => draw_candles(f, app, app.selected, chunks[0]),
+        }
+    }
     draw_legend(f, app, chunks[1]);
 }
 
-fn draw_chart(f: &mut Frame, app: &App, area: Rect) {
-    let datasets: Vec<Dataset> = app
-        .symbols
+fn draw_chart(f: &mut Frame, app: &App, symbols: &[usize], title: &str, area: Rect) {
+    let (start, end) = app.time_bounds(Utc::now());
+    let (x_min, x_max) = (x_value(start), x_value(end));
+
+    let series: Vec<(usize, Vec<(f64, f64)>)> = symbols
         .iter()
-        .enumerate()
-        .filter_map(|(i, sym)| {
-            let data = &app.chart_data[i];
-            if data.is_empty() {
-                return None;
-            }
+        .map(|&i| (i, app.visible_series(i, x_min)))
+        .filter(|(_, data)| !data.is_empty())
+        .collect();
+    let (y_min, y_max) = y_bounds(series.iter().flat_map(|(_, data)| data.iter().map(|&(_, y)| y)));
+
+    let datasets: Vec<Dataset> = series
+        .iter()
+        .map(|(i, data)| {
+            Dataset::default()
+                .name(app.symbols[*i].clone())
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(COLORS[i % COLORS.len()]))
+                .data(data)
+        })
+        .collect();
+
+    let y_labels = if app.percent {
+        percent_labels(y_min, y_max)
+    } else {
+        price_labels(y_min, y_max)
+    };
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(title.to_string())
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::White)),
+        )
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::DarkGray))
+                .labels(time_labels(app, start, end))
+                .bounds([x_min, x_max]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::DarkGray))
+                .labels(y_labels)
+                .bounds([y_min, y_max]),
+        );
+
+    f.render_widget(chart, area);
+}
+
+// Cents, or as many decimals as it takes to tell the labels apart on sub-cent prices
+fn price_labels(y_min: f64, y_max: f64) -> Vec<Span<'static>> {
+    let step = (y_max - y_min) / 2.0;
+    let decimals = (1 - step.log10().floor() as i64).clamp(2, 12) as usize;
+    vec![
+        Span::raw(format!("${:.*}", decimals, y_min)),
+        Span::raw(format!("${:.*}", decimals, y_min + step)),
+        Span::raw(format!("${:.*}", decimals, y_max)),
+    ]
+}
+
+fn percent_labels(y_min: f64, y_max: f64) -> Vec<Span<'static>> {
+    vec![
+        Span::raw(format!("{:+.2}%", y_min)),
+        Span::raw(format!("{:+.2}%", (y_min + y_max) / 2.0)),
+        Span::raw(format!("{:+.2}%", y_max)),
+    ]
+}
 
-            let color = COLORS[i % COLORS.len()];
+fn time_labels(app: &App, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<Span<'static>> {
+    // Windows longer than a day span sessions, so name the weekday too
+    let time_format = if app.window > TimeDelta::days(1) { "%a %H:%M" } else { "%H:%M" };
+    [start, start + (end - start) / 2, end]
+        .iter()
+        .map(|t| Span::raw(t.with_timezone(&Local).format(time_format).to_string()))
+        .collect()
+}
+
+/// A candle's color plus the wick and body segments it is drawn from.
+type CandleShape = (Color, [(f64, f64); 2], [(f64, f64); 2]);
+
+// Normalized candles are rebased to the first visible open, as lines are to their first point
+fn candle_shapes(app: &App, candles: &[&Candle]) -> Vec<CandleShape> {
+    let base = candles
+        .first()
+        .and_then(|c| c.open.to_f64())
+        .filter(|&base| app.percent && base != 0.0);
+    let scale = |value: Decimal| {
+        let value = value.to_f64()?;
+        Some(base.map_or(value, |base| (value / base - 1.0) * 100.0))
+    };
+
+    candles
+        .iter()
+        .filter_map(|c| {
+            let x = x_value(c.start + app.interval / 2);
+            let (open, close) = (scale(c.open)?, scale(c.close)?);
+            let wick = [(x, scale(c.low)?), (x, scale(c.high)?)];
+            let body = [(x, open.min(close)), (x, open.max(close))];
+            let color = if close >= open { Color::Green } else { Color::Red };
+            Some((color, wick, body))
+        })
+        .collect()
+}
+
+// Each candle is a thin braille wick from low to high and a block-marker body from open to close
+fn draw_candles(f: &mut Frame, app: &App, index: usize, area: Rect) {
+    let (start, end) = app.time_bounds(Utc::now());
+    let (x_min, x_max) = (x_value(start), x_value(end));
+    let symbol = &app.symbols[index];
+    let color = COLORS[index % COLORS.len()];
 
-            Some(
+    let candles: Vec<&Candle> = app
+        .candles
+        .get(symbol)
+        .map(|c| c.iter().filter(|c| c.start + app.interval > start).collect())
+        .unwrap_or_default();
+
+    let shapes = candle_shapes(app, &candles);
+    let (y_min, y_max) = y_bounds(shapes.iter().flat_map(|(_, wick, _)| [wick[0].1, wick[1].1]));
+    let y_labels = if app.percent {
+        percent_labels(y_min, y_max)
+    } else {
+        price_labels(y_min, y_max)
+    };
+
+    let datasets: Vec<Dataset> = shapes
+        .iter()
+        .flat_map(|(color, wick, body)| {
+            [
                 Dataset::default()
-                    .name(sym.clone())
                     .marker(Marker::Braille)
                     .graph_type(GraphType::Line)
-                    .style(Style::default().fg(color))
-                    .data(data),
-            )
+                    .style(Style::default().fg(*color))
+                    .data(wick),
+                Dataset::default()
+                    .marker(Marker::Block)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(*color))
+                    .data(body),
+            ]
         })
         .collect();
 
-    let y_labels = vec![
-        Span::raw(format!("${:.2}", app.y_min)),
-        Span::raw(format!("${:.2}", (app.y_min + app.y_max) / 2.0)),
-        Span::raw(format!("${:.2}", app.y_max)),
-    ];
-
     let chart = Chart::new(datasets)
         .block(
             Block::default()
-                .title(" Live Prices ")
+                .title(Span::styled(
+                    format!(" {} candles ({}) ", symbol, interval_label(app.interval)),
+                    Style::default().fg(color),
+                ))
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(Color::White)),
         )
         .x_axis(
             Axis::default()
                 .style(Style::default().fg(Color::DarkGray))
-                .bounds([0.0, MAX_POINTS as f64]),
+                .labels(time_labels(app, start, end))
+                .bounds([x_min, x_max]),
         )
         .y_axis(
             Axis::default()
                 .style(Style::default().fg(Color::DarkGray))
                 .labels(y_labels)
-                .bounds([app.y_min, app.y_max]),
+                .bounds([y_min, y_max]),
         );
 
     f.render_widget(chart, area);
 }
 
 fn draw_legend(f: &mut Frame, app: &App, area: Rect) {
-    let legend_items: Vec<Span> = app
+    let mut legend_items: Vec<Span> = app
         .symbols
         .iter()
         .enumerate()
         .flat_map(|(i, sym)| {
-            let color = COLORS[i % COLORS.len()];
+            let mut color = COLORS[i % COLORS.len()];
+            if app.mode == ChartMode::Candles && !app.grid && i != app.selected {
+                color = Color::DarkGray;
+            }
             let price = app.prices.get(sym)
                 .and_then(|h| h.back())
-                .map(|p| format!("${:.2}", p))
+                .map(|&(_, p)| format!("${}", unit_price(p)))
                 .unwrap_or_else(|| "---".to_string());
 
             vec![
@@ -231,6 +722,10 @@ fn draw_legend(f: &mut Frame, app: &App, area: Rect) {
             ]
         })
         .collect();
+    legend_items.push(Span::styled(
+        "[c] line/candles  [p] $/%  [g] grid  [tab] symbol  [q] quit",
+        Style::default().fg(Color::DarkGray),
+    ));
 
     let legend = Paragraph::new(Line::from(legend_items))
         .block(Block::default().borders(Borders::ALL).title(" Legend "))
@@ -238,3 +733,202 @@ fn draw_legend(f: &mut Frame, app: &App, area: Rect) {
 
     f.render_widget(legend, area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn bar(timestamp: &str) -> Bar {
+        Bar {
+            timestamp: timestamp.parse().unwrap(),
+            open: dec!(1),
+            high: dec!(1),
+            low: dec!(1),
+            close: dec!(1),
+            volume: dec!(1),
+        }
+    }
+
+    #[test]
+    fn test_lookback_counts_back_from_latest_bar() {
+        let bars = vec![
+            bar("2024-03-01T20:30:00Z"),
+            bar("2024-03-01T23:59:00Z"),
+            bar("2024-03-04T13:00:00Z"),
+            bar("2024-03-04T13:59:00Z"),
+        ];
+
+        let kept = within_lookback(bars, Lookback::Hour);
+        let times: Vec<String> = kept.iter().map(|b| b.timestamp.to_rfc3339()).collect();
+        assert_eq!(times, vec!["2024-03-04T13:00:00+00:00", "2024-03-04T13:59:00+00:00"]);
+    }
+
+    #[test]
+    fn test_insert_point_orders_and_revises() {
+        let t = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        let mut history = VecDeque::new();
+
+        let slot = TimeDelta::seconds(1);
+
+        insert_point(&mut history, t("2024-03-04T14:02:00Z"), dec!(3), slot);
+        insert_point(&mut history, t("2024-03-04T14:00:00Z"), dec!(1), slot);
+        insert_point(&mut history, t("2024-03-04T14:02:00Z"), dec!(4), slot);
+
+        let prices: Vec<Decimal> = history.iter().map(|&(_, p)| p).collect();
+        assert_eq!(prices, vec![dec!(1), dec!(4)]);
+    }
+
+    #[test]
+    fn test_insert_point_keeps_latest_per_slot() {
+        let t = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        let mut history = VecDeque::new();
+        let slot = TimeDelta::seconds(10);
+
+        insert_point(&mut history, t("2024-03-04T14:00:01Z"), dec!(1), slot);
+        insert_point(&mut history, t("2024-03-04T14:00:05Z"), dec!(2), slot);
+        insert_point(&mut history, t("2024-03-04T14:00:03Z"), dec!(9), slot);
+        insert_point(&mut history, t("2024-03-04T14:00:12Z"), dec!(3), slot);
+
+        let points: Vec<(DateTime<Utc>, Decimal)> = history.into_iter().collect();
+        assert_eq!(
+            points,
+            vec![(t("2024-03-04T14:00:05Z"), dec!(2)), (t("2024-03-04T14:00:12Z"), dec!(3))]
+        );
+    }
+
+    #[test]
+    fn test_time_bounds_fall_back_to_last_point_when_idle() {
+        let mut app = App::new(vec!["AAPL".to_string()], TimeDelta::minutes(15), TimeDelta::minutes(1));
+        let last = "2024-03-01T20:59:00Z".parse::<DateTime<Utc>>().unwrap();
+        app.update(PriceUpdate {
+            symbol: "AAPL".to_string(),
+            price: dec!(180),
+            time: last,
+        });
+
+        let (start, end) = app.time_bounds(last + TimeDelta::days(2));
+        assert_eq!(end, last);
+        assert_eq!(start, last - TimeDelta::minutes(15));
+
+        let now = last + TimeDelta::minutes(5);
+        assert_eq!(app.time_bounds(now).1, now);
+    }
+
+    #[test]
+    fn test_trades_aggregate_into_candles() {
+        let mut app = App::new(vec!["AAPL".to_string()], TimeDelta::hours(1), TimeDelta::minutes(5));
+        let trades = [
+            ("2024-03-04T14:00:10Z", dec!(100)),
+            ("2024-03-04T14:01:00Z", dec!(103)),
+            ("2024-03-04T14:03:00Z", dec!(99)),
+            ("2024-03-04T14:04:59Z", dec!(101)),
+            ("2024-03-04T14:05:00Z", dec!(102)),
+        ];
+        for (time, price) in trades {
+            app.apply(ChartUpdate::Price(PriceUpdate {
+                symbol: "AAPL".to_string(),
+                price,
+                time: time.parse().unwrap(),
+            }));
+        }
+
+        let candles = &app.candles["AAPL"];
+        assert_eq!(candles.len(), 2);
+        let first = candles[0];
+        assert_eq!(first.start, "2024-03-04T14:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(
+            (first.open, first.high, first.low, first.close),
+            (dec!(100), dec!(103), dec!(99), dec!(101))
+        );
+        assert_eq!(candles[1].open, dec!(102));
+    }
+
+    #[test]
+    fn test_percent_series_rebases_to_first_visible_point() {
+        let mut app = App::new(
+            vec!["AAPL".to_string(), "F".to_string()],
+            TimeDelta::hours(1),
+            TimeDelta::minutes(1),
+        );
+        let points = [
+            ("AAPL", "2024-03-04T14:00:00Z", dec!(200)),
+            ("AAPL", "2024-03-04T14:01:00Z", dec!(202)),
+            ("F", "2024-03-04T14:00:00Z", dec!(12)),
+            ("F", "2024-03-04T14:01:00Z", dec!(11.88)),
+        ];
+        for (symbol, time, price) in points {
+            app.update(PriceUpdate {
+                symbol: symbol.to_string(),
+                price,
+                time: time.parse().unwrap(),
+            });
+        }
+
+        app.refresh();
+        assert_eq!(app.visible_series(1, 0.0)[1].1, 11.88);
+
+        app.toggle_percent();
+        let aapl: Vec<f64> = app.visible_series(0, 0.0).iter().map(|&(_, y)| y).collect();
+        let f: Vec<f64> = app.visible_series(1, 0.0).iter().map(|&(_, y)| y).collect();
+        assert_eq!(aapl[0], 0.0);
+        assert!((aapl[1] - 1.0).abs() < 1e-9);
+        assert!((f[1] + 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_y_bounds_scale_to_sub_cent_prices() {
+        let (low, high) = y_bounds([0.00001234, 0.00001234]);
+        assert!(low < 0.00001234 && high > 0.00001234);
+        assert!(high - low < 1e-7);
+
+        let (low, high) = y_bounds([187.0, 189.0]);
+        assert!((low - 186.9).abs() < 1e-9 && (high - 189.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_late_bar_revision_keeps_candle_close() {
+        let mut app = App::new(vec!["AAPL".to_string()], TimeDelta::hours(1), TimeDelta::minutes(5));
+        let mut minute = |time: &str, close| {
+            let mut bar = bar(time);
+            bar.close = close;
+            app.apply(ChartUpdate::Bar {
+                symbol: "AAPL".to_string(),
+                bar,
+            });
+        };
+        minute("2024-03-04T14:01:00Z", dec!(101));
+        minute("2024-03-04T14:02:00Z", dec!(102));
+        // An updatedBars correction for 14:01 arrives after the 14:02 bar
+        minute("2024-03-04T14:01:00Z", dec!(100.5));
+
+        assert_eq!(app.candles["AAPL"][0].close, dec!(102));
+    }
+
+    #[test]
+    fn test_percent_candles_rebase_to_first_open() {
+        let mut app = App::new(vec!["F".to_string()], TimeDelta::hours(1), TimeDelta::minutes(1));
+        let minutes = [
+            ("2024-03-04T14:00:00Z", dec!(12), dec!(12.12)),
+            ("2024-03-04T14:01:00Z", dec!(12.12), dec!(11.88)),
+        ];
+        for (time, open, close) in minutes {
+            let mut bar = bar(time);
+            (bar.open, bar.high, bar.low, bar.close) = (open, open.max(close), open.min(close), close);
+            app.apply(ChartUpdate::Bar {
+                symbol: "F".to_string(),
+                bar,
+            });
+        }
+        app.toggle_percent();
+
+        let candles: Vec<&Candle> = app.candles["F"].iter().collect();
+        let shapes = candle_shapes(&app, &candles);
+        let (_, first_wick, _) = shapes[0];
+        let (color, _, second_body) = shapes[1];
+        assert_eq!(first_wick[0].1, 0.0);
+        assert!((first_wick[1].1 - 1.0).abs() < 1e-9);
+        assert!((second_body[0].1 + 1.0).abs() < 1e-9);
+        assert_eq!(color, Color::Red);
+    }
+}
@@ -2,21 +2,30 @@ use std::collections::HashMap;
 use std::io::{self, Write};
 use std::time::Duration;
 
+use chrono::{DateTime, Local, Utc};
 use futures::{SinkExt, StreamExt};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use tokio::time::{sleep, timeout};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
 use tokio::sync::mpsc;
 
+use crate::alpaca_api::{is_crypto, AlpacaClient, Bar, Feed};
+use crate::cli::output::unit_price;
 use crate::error::{AppError, AppResult};
-use crate::tui::PriceUpdate;
+use crate::tui::{ChartUpdate, PriceUpdate};
 
-const STREAM_URL: &str = "wss://stream.data.alpaca.markets/v2/iex";
-const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+const CRYPTO_STREAM_URL: &str = "wss://stream.data.alpaca.markets/v1beta3/crypto/us";
+pub const MAX_RECONNECT_ATTEMPTS: u32 = 5;
 const INITIAL_BACKOFF_MS: u64 = 1000;
 const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// Delay before reconnect number `retry` (starting at 1), doubling each time.
+pub fn reconnect_backoff(retry: u32) -> Duration {
+    Duration::from_millis(INITIAL_BACKOFF_MS * 2_u64.pow(retry - 1))
+}
+
 #[derive(Serialize)]
 struct AuthMessage<'a> {
     action: &'static str,
@@ -27,35 +36,120 @@ struct AuthMessage<'a> {
 #[derive(Serialize)]
 struct SubscribeMessage {
     action: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     trades: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     quotes: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    bars: Vec<String>,
+    #[serde(rename = "dailyBars", skip_serializing_if = "Vec::is_empty")]
+    daily_bars: Vec<String>,
+    #[serde(rename = "updatedBars", skip_serializing_if = "Vec::is_empty")]
+    updated_bars: Vec<String>,
+}
+
+impl SubscribeMessage {
+    fn new(symbols: &[String], channels: Channels) -> Self {
+        let pick = |enabled: bool| if enabled { symbols.to_vec() } else { Vec::new() };
+        Self {
+            action: "subscribe",
+            trades: pick(channels.trades),
+            quotes: pick(channels.quotes),
+            bars: pick(channels.bars),
+            daily_bars: pick(channels.daily_bars),
+            // Corrections for minute bars that late trades have changed
+            updated_bars: pick(channels.bars),
+        }
+    }
+}
+
+/// Which market-data channels to subscribe to.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Channels {
+    pub trades: bool,
+    pub quotes: bool,
+    pub bars: bool,
+    pub daily_bars: bool,
+}
+
+impl Channels {
+    pub fn from_args(values: &[String]) -> Self {
+        Self {
+            trades: values.iter().any(|v| v == "trades"),
+            quotes: values.iter().any(|v| v == "quotes"),
+            bars: values.iter().any(|v| v == "bars"),
+            daily_bars: values.iter().any(|v| v == "daily-bars"),
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
-struct StreamMessage {
-    #[serde(rename = "T")]
-    msg_type: String,
-    msg: Option<String>,
+#[serde(tag = "T")]
+enum StreamMessage {
+    #[serde(rename = "t")]
+    Trade {
+        #[serde(rename = "S")]
+        symbol: String,
+        #[serde(rename = "p")]
+        price: Decimal,
+        #[serde(rename = "s")]
+        size: Decimal,
+        #[serde(rename = "t")]
+        timestamp: DateTime<Utc>,
+    },
+    #[serde(rename = "q")]
+    Quote {
+        #[serde(rename = "S")]
+        symbol: String,
+        #[serde(rename = "bp")]
+        bid_price: Decimal,
+        #[serde(rename = "ap")]
+        ask_price: Decimal,
+        #[serde(rename = "t")]
+        timestamp: DateTime<Utc>,
+    },
+    #[serde(rename = "b")]
+    Bar(BarMessage),
+    #[serde(rename = "d")]
+    DailyBar(BarMessage),
+    /// A minute bar revised after late trades arrived.
+    #[serde(rename = "u")]
+    UpdatedBar(BarMessage),
+    #[serde(rename = "error")]
+    Error { msg: String },
+    // Control messages such as `success` and `subscription`
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize, Debug)]
+struct BarMessage {
     #[serde(rename = "S")]
-    symbol: Option<String>,
-    // #[serde(rename = "p")]
-    // price: Option<f64>,
-    // #[serde(rename = "s")]
-    // size: Option<u64>,
-    #[serde(rename = "bp")]
-    bid_price: Option<f64>,
-    #[serde(rename = "ap")]
-    ask_price: Option<f64>,
+    symbol: String,
+    #[serde(flatten)]
+    bar: Bar,
 }
 
 #[derive(Clone)]
 struct QuoteState {
-    bid: f64,
-    ask: f64,
+    bid: Decimal,
+    ask: Decimal,
+}
+
+#[derive(Clone)]
+struct TradeState {
+    price: Decimal,
+    size: Decimal,
+    time: DateTime<Utc>,
+    /// Session volume: the snapshot's daily volume plus every trade streamed since.
+    volume: Decimal,
 }
 
 struct DisplayState {
     quotes: HashMap<String, QuoteState>,
+    trades: HashMap<String, TradeState>,
+    bars: HashMap<String, Bar>,
+    daily_bars: HashMap<String, Bar>,
     symbols: Vec<String>,
     lines_printed: usize,
 }
@@ -64,16 +158,65 @@ impl DisplayState {
     fn new(symbols: Vec<String>) -> Self {
         Self {
             quotes: HashMap::new(),
+            trades: HashMap::new(),
+            bars: HashMap::new(),
+            daily_bars: HashMap::new(),
             symbols,
             lines_printed: 0,
         }
     }
 
-    fn update_quote(&mut self, symbol: String, bid: f64, ask: f64) {
+    fn update_quote(&mut self, symbol: String, bid: Decimal, ask: Decimal) {
         self.quotes.insert(symbol, QuoteState { bid, ask });
         self.redraw();
     }
 
+    fn update_trade(&mut self, symbol: String, price: Decimal, size: Decimal, time: DateTime<Utc>) {
+        let volume = self.trades.get(&symbol).map_or(Decimal::ZERO, |t| t.volume) + size;
+        self.trades.insert(symbol, TradeState { price, size, time, volume });
+        self.redraw();
+    }
+
+    // An older revision never replaces a newer bar
+    fn update_bar(&mut self, symbol: String, bar: Bar) {
+        if self.bars.get(&symbol).is_some_and(|b| b.timestamp > bar.timestamp) {
+            return;
+        }
+        self.bars.insert(symbol, bar);
+        self.redraw();
+    }
+
+    fn update_daily_bar(&mut self, symbol: String, bar: Bar) {
+        self.daily_bars.insert(symbol, bar);
+        self.redraw();
+    }
+
+    fn format_line(&self, sym: &str) -> String {
+        let trade = self.trades.get(sym).map(|t| {
+            format!(
+                "last ${} x {} @ {} | vol {}",
+                unit_price(t.price),
+                t.size.normalize(),
+                t.time.with_timezone(&Local).format("%H:%M:%S"),
+                t.volume.normalize()
+            )
+        });
+        let quote = self
+            .quotes
+            .get(sym)
+            .map(|q| format!("${} bid / ${} ask", unit_price(q.bid), unit_price(q.ask)));
+
+        let bar = self.bars.get(sym).map(|b| format!("1m {}", format_bar(b)));
+        let daily_bar = self.daily_bars.get(sym).map(|b| format!("day {}", format_bar(b)));
+
+        let parts: Vec<String> = [trade, quote, bar, daily_bar].into_iter().flatten().collect();
+        if parts.is_empty() {
+            format!("[{sym}] waiting...")
+        } else {
+            format!("[{sym}] {}", parts.join(" | "))
+        }
+    }
+
     fn redraw(&mut self) {
         // Move cursor up to overwrite previous output
         if self.lines_printed > 0 {
@@ -84,11 +227,7 @@ impl DisplayState {
         for sym in &self.symbols {
             // Clear line and print
             print!("\x1B[K");  // Clear from cursor to end of line
-            if let Some(q) = self.quotes.get(sym) {
-                println!("[{sym}] ${:.2} bid / ${:.2} ask", q.bid, q.ask);
-            } else {
-                println!("[{sym}] waiting...");
-            }
+            println!("{}", self.format_line(sym));
             lines += 1;
         }
 
@@ -97,13 +236,86 @@ impl DisplayState {
     }
 }
 
+/// Crypto pairs have their own stream; the stock feed only applies to equities.
+pub fn stream_url(symbols: &[String], feed: Feed) -> AppResult<String> {
+    let crypto = symbols.iter().filter(|s| is_crypto(s)).count();
+    if crypto == 0 {
+        Ok(feed.stream_url())
+    } else if crypto == symbols.len() {
+        Ok(CRYPTO_STREAM_URL.to_string())
+    } else {
+        Err(AppError::Config(
+            "Stocks and crypto come from different streams; run them separately".into(),
+        ))
+    }
+}
+
+// Fill the display from REST snapshots so quiet streams show the last known values straight away.
+// The snapshot comes from the stream's feed, so the session volume only counts trades on that feed.
+async fn seed_display(client: &AlpacaClient, display: &mut DisplayState, channels: Channels, feed: Feed) {
+    let snapshots = futures::future::join_all(
+        display.symbols.iter().map(|symbol| client.fetch_snapshot(symbol, Some(feed))),
+    )
+    .await;
+
+    for (symbol, snapshot) in display.symbols.clone().into_iter().zip(snapshots) {
+        let Ok(snapshot) = snapshot else { continue };
+
+        if let (true, Some(trade)) = (channels.trades, snapshot.latest_trade) {
+            let volume = snapshot.daily_bar.as_ref().map_or(Decimal::ZERO, |b| b.volume);
+            let state = TradeState {
+                price: trade.price,
+                size: trade.size,
+                time: trade.timestamp,
+                volume,
+            };
+            display.trades.insert(symbol.clone(), state);
+        }
+        if let (true, Some(quote)) = (channels.quotes, snapshot.latest_quote) {
+            let state = QuoteState {
+                bid: quote.bid_price,
+                ask: quote.ask_price,
+            };
+            display.quotes.insert(symbol.clone(), state);
+        }
+        if let (true, Some(bar)) = (channels.bars, snapshot.minute_bar) {
+            display.bars.insert(symbol.clone(), bar);
+        }
+        if let (true, Some(bar)) = (channels.daily_bars, snapshot.daily_bar) {
+            display.daily_bars.insert(symbol, bar);
+        }
+    }
+}
+
+fn format_bar(bar: &Bar) -> String {
+    format!(
+        "O {} H {} L {} C {} V {}",
+        unit_price(bar.open),
+        unit_price(bar.high),
+        unit_price(bar.low),
+        unit_price(bar.close),
+        bar.volume.normalize()
+    )
+}
+
 enum StreamExit {
     Shutdown,
     Disconnected,
 }
 
-pub async fn stream_trades(api_key: &str, api_secret: &str, symbols: Vec<String>) -> AppResult<()> {
+pub async fn stream_trades(
+    client: &AlpacaClient,
+    api_key: &str,
+    api_secret: &str,
+    symbols: Vec<String>,
+    channels: Channels,
+    feed: Feed,
+) -> AppResult<()> {
+    let stream_url = stream_url(&symbols, feed)?;
     let mut attempt = 0;
+    // Kept across reconnects so the session volume keeps counting
+    let mut display = DisplayState::new(symbols.clone());
+    seed_display(client, &mut display, channels, feed).await;
 
     loop {
         attempt += 1;
@@ -115,14 +327,17 @@ pub async fn stream_trades(api_key: &str, api_secret: &str, symbols: Vec<String>
         }
 
         if attempt > 1 {
-            let backoff = INITIAL_BACKOFF_MS * 2_u64.pow(attempt - 2);
-            println!("Reconnecting in {backoff}ms (attempt {attempt}/{MAX_RECONNECT_ATTEMPTS})...");
-            sleep(Duration::from_millis(backoff)).await;
+            let backoff = reconnect_backoff(attempt - 1);
+            println!(
+                "Reconnecting in {}ms (attempt {attempt}/{MAX_RECONNECT_ATTEMPTS})...",
+                backoff.as_millis()
+            );
+            sleep(backoff).await;
         }
 
-        println!("Connecting to...");
+        println!("Connecting to {stream_url}...");
 
-        let ws_stream = match connect_async(STREAM_URL).await {
+        let ws_stream = match connect_async(stream_url.as_str()).await {
             Ok((stream, _)) => stream,
             Err(e) => {
                 eprintln!("Connection failed: {e}");
@@ -166,11 +381,7 @@ pub async fn stream_trades(api_key: &str, api_secret: &str, symbols: Vec<String>
             }
         }
 
-        let subscribe = SubscribeMessage {
-            action: "subscribe",
-            trades: symbols.clone(),
-            quotes: symbols.clone(),
-        };
+        let subscribe = SubscribeMessage::new(&symbols, channels);
         if let Err(e) = write.send(Message::Text(serde_json::to_string(&subscribe)?)).await {
             eprintln!("Subscribe send failed: {e}");
             continue;
@@ -180,8 +391,7 @@ pub async fn stream_trades(api_key: &str, api_secret: &str, symbols: Vec<String>
         println!("Streaming... (Ctrl+C to stop)\n");
 
         attempt = 0;
-
-        let mut display = DisplayState::new(symbols.clone());
+        display.lines_printed = 0;
 
         let exit_reason = process_messages(&mut read, &mut display).await;
 
@@ -236,35 +446,38 @@ where
 fn handle_text_message(text: &str, display: &mut DisplayState) {
     if let Ok(messages) = serde_json::from_str::<Vec<StreamMessage>>(text) {
         for m in messages {
-            match m.msg_type.as_str() {
-                "q" => {
-                    if let (Some(sym), Some(bp), Some(ap)) =
-                        (m.symbol, m.bid_price, m.ask_price)
-                    {
-                        display.update_quote(sym, bp, ap);
-                    }
+            match m {
+                StreamMessage::Quote { symbol, bid_price, ask_price, .. } => {
+                    display.update_quote(symbol, bid_price, ask_price);
                 }
-                "t" => {
-                    // Trades could be shown separately or update a "last trade" field
+                StreamMessage::Trade { symbol, price, size, timestamp } => {
+                    display.update_trade(symbol, price, size, timestamp);
                 }
-                "error" => {
-                    if let Some(msg) = &m.msg {
-                        eprintln!("[error: {msg}]");
-                    }
+                StreamMessage::Bar(m) | StreamMessage::UpdatedBar(m) => {
+                    display.update_bar(m.symbol, m.bar);
                 }
-                _ => {}
+                StreamMessage::DailyBar(m) => {
+                    display.update_daily_bar(m.symbol, m.bar);
+                }
+                StreamMessage::Error { msg } => {
+                    eprintln!("[error: {msg}]");
+                }
+                StreamMessage::Other => {}
             }
         }
     }
 }
 
-/// Stream prices to a channel (for TUI mode)
+/// Stream quote mids, trades or minute bars to a channel (for TUI mode)
 pub async fn stream_to_channel(
     api_key: &str,
     api_secret: &str,
     symbols: Vec<String>,
-    tx: mpsc::Sender<PriceUpdate>,
+    channels: Channels,
+    feed: Feed,
+    tx: mpsc::Sender<ChartUpdate>,
 ) -> AppResult<()> {
+    let stream_url = stream_url(&symbols, feed)?;
     let mut attempt = 0;
 
     loop {
@@ -277,11 +490,10 @@ pub async fn stream_to_channel(
         }
 
         if attempt > 1 {
-            let backoff = INITIAL_BACKOFF_MS * 2_u64.pow(attempt - 2);
-            sleep(Duration::from_millis(backoff)).await;
+            sleep(reconnect_backoff(attempt - 1)).await;
         }
 
-        let ws_stream = match connect_async(STREAM_URL).await {
+        let ws_stream = match connect_async(stream_url.as_str()).await {
             Ok((stream, _)) => stream,
             Err(_) => continue,
         };
@@ -306,11 +518,7 @@ pub async fn stream_to_channel(
             continue;
         }
 
-        let subscribe = SubscribeMessage {
-            action: "subscribe",
-            trades: symbols.clone(),
-            quotes: symbols.clone(),
-        };
+        let subscribe = SubscribeMessage::new(&symbols, channels);
 
         if write.send(Message::Text(serde_json::to_string(&subscribe)?)).await.is_err() {
             continue;
@@ -325,17 +533,27 @@ pub async fn stream_to_channel(
                 Ok(Some(Ok(Message::Text(text)))) => {
                     if let Ok(messages) = serde_json::from_str::<Vec<StreamMessage>>(&text) {
                         for m in messages {
-                            if m.msg_type == "q" {
-                                if let (Some(sym), Some(bp), Some(ap)) =
-                                    (m.symbol, m.bid_price, m.ask_price)
-                                {
-                                    let mid_price = (bp + ap) / 2.0;
-                                    let _ = tx.send(PriceUpdate {
-                                        symbol: sym,
-                                        price: mid_price,
-                                    }).await;
+                            let update = match m {
+                                StreamMessage::Quote { symbol, bid_price, ask_price, timestamp } => {
+                                    ChartUpdate::Price(PriceUpdate {
+                                        symbol,
+                                        price: (bid_price + ask_price) / Decimal::TWO,
+                                        time: timestamp,
+                                    })
                                 }
-                            }
+                                StreamMessage::Trade { symbol, price, timestamp, .. } => {
+                                    ChartUpdate::Price(PriceUpdate {
+                                        symbol,
+                                        price,
+                                        time: timestamp,
+                                    })
+                                }
+                                StreamMessage::Bar(m) | StreamMessage::UpdatedBar(m) => {
+                                    ChartUpdate::Bar { symbol: m.symbol, bar: m.bar }
+                                }
+                                _ => continue,
+                            };
+                            let _ = tx.send(update).await;
                         }
                     }
                 }
@@ -347,3 +565,62 @@ pub async fn stream_to_channel(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_parse_mixed_messages() {
+        let text = r#"[
+            {"T": "success", "msg": "authenticated"},
+            {"T": "t", "S": "AAPL", "i": 52983525029461, "x": "V", "p": 187.12, "s": 100,
+             "c": ["@"], "z": "C", "t": "2024-03-01T15:30:00.123456789Z"},
+            {"T": "q", "S": "AAPL", "bx": "V", "bp": 187.1, "bs": 2, "ax": "V", "ap": 187.14,
+             "as": 3, "c": ["R"], "z": "C", "t": "2024-03-01T15:30:00.2Z"},
+            {"T": "b", "S": "AAPL", "o": 187.0, "h": 187.3, "l": 186.9, "c": 187.12, "v": 12345,
+             "t": "2024-03-01T15:29:00Z", "n": 120, "vw": 187.1}
+        ]"#;
+
+        let messages: Vec<StreamMessage> = serde_json::from_str(text).unwrap();
+        assert!(matches!(messages[0], StreamMessage::Other));
+        assert!(matches!(messages[1], StreamMessage::Trade { size, .. } if size == dec!(100)));
+        assert!(matches!(messages[2], StreamMessage::Quote { ask_price, .. } if ask_price == dec!(187.14)));
+        match &messages[3] {
+            StreamMessage::Bar(m) => {
+                assert_eq!(m.symbol, "AAPL");
+                assert_eq!(m.bar.close, dec!(187.12));
+            }
+            other => panic!("expected a bar, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_trades_add_to_session_volume() {
+        let mut display = DisplayState::new(vec!["AAPL".to_string()]);
+        let seeded = TradeState {
+            price: dec!(187.0),
+            size: dec!(10),
+            time: "2024-03-01T15:29:59Z".parse().unwrap(),
+            volume: dec!(50000),
+        };
+        display.trades.insert("AAPL".to_string(), seeded);
+
+        let text = r#"[
+            {"T": "t", "S": "AAPL", "p": 187.1, "s": 100, "t": "2024-03-01T15:30:00Z"},
+            {"T": "t", "S": "AAPL", "p": 187.12, "s": 25, "t": "2024-03-01T15:30:01Z"}
+        ]"#;
+        let messages: Vec<StreamMessage> = serde_json::from_str(text).unwrap();
+        for message in messages {
+            if let StreamMessage::Trade { symbol, price, size, timestamp } = message {
+                display.update_trade(symbol, price, size, timestamp);
+            }
+        }
+
+        let trade = &display.trades["AAPL"];
+        assert_eq!(trade.price, dec!(187.12));
+        assert_eq!(trade.size, dec!(25));
+        assert_eq!(trade.volume, dec!(50125));
+    }
+}